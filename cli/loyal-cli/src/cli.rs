@@ -65,13 +65,21 @@ pub(crate) enum OutputFormat {
 #[derive(Subcommand, Debug)]
 pub(crate) enum Command {
     Display(TargetArgs),
-    Delegate(TargetArgs),
+    Delegate(DelegateArgs),
     Undelegate(UndelegateArgs),
     WaitDelegate(WaitArgs),
     WaitUndelegate(WaitArgs),
     Shield(AmountArgs),
     Unshield(AmountArgs),
     TransferUsername(TransferUsernameArgs),
+    /// Withdraw from your username deposit straight to your wallet.
+    ClaimUsername(ClaimUsernameArgs),
+    /// Escrow SOL for a Telegram username in the telegram-transfer program.
+    Deposit(DepositArgs),
+    /// Pull SOL back out of your telegram-transfer deposit.
+    Refund(RefundArgs),
+    /// Claim a telegram-transfer deposit with your verified Telegram session.
+    Claim(ClaimArgs),
 }
 
 #[derive(Args, Debug, Clone)]
@@ -86,6 +94,15 @@ pub(crate) struct TargetArgs {
     pub(crate) username: Option<String>,
 }
 
+#[derive(Args, Debug)]
+pub(crate) struct DelegateArgs {
+    #[command(flatten)]
+    pub(crate) target: TargetArgs,
+
+    #[arg(long)]
+    pub(crate) session: Option<String>,
+}
+
 #[derive(Args, Debug)]
 pub(crate) struct UndelegateArgs {
     #[command(flatten)]
@@ -116,6 +133,53 @@ pub(crate) struct AmountArgs {
     pub(crate) amount: u64,
 }
 
+#[derive(Args, Debug)]
+pub(crate) struct DepositArgs {
+    #[arg(long)]
+    pub(crate) username: String,
+
+    /// Lamports to escrow.
+    #[arg(long)]
+    pub(crate) amount: u64,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct RefundArgs {
+    #[arg(long)]
+    pub(crate) username: String,
+
+    /// Lamports to refund; defaults to the whole balance.
+    #[arg(long)]
+    pub(crate) amount: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ClaimArgs {
+    /// Wallet that made the deposit.
+    #[arg(long)]
+    pub(crate) depositor: String,
+
+    #[arg(long)]
+    pub(crate) username: String,
+
+    /// Lamports to claim; defaults to the whole balance.
+    #[arg(long)]
+    pub(crate) amount: Option<u64>,
+}
+
+#[derive(Args, Debug)]
+pub(crate) struct ClaimUsernameArgs {
+    #[arg(long, default_value = NATIVE_MINT_STR)]
+    pub(crate) mint: String,
+
+    #[arg(long)]
+    pub(crate) username: String,
+
+    /// Raw token units to claim; defaults to the whole balance.
+    #[arg(long)]
+    pub(crate) amount: Option<u64>,
+}
+
 #[derive(Args, Debug)]
 pub(crate) struct TransferUsernameArgs {
     #[arg(long, default_value = NATIVE_MINT_STR)]
@@ -4,18 +4,23 @@ use serde_json::json;
 use spl_associated_token_account_client::address::get_associated_token_address_with_program_id;
 use spl_token::native_mint::id as native_mint_id;
 use std::time::Duration;
+use telegram_transfer::client as transfer_client;
 
 use crate::{
     auth::get_delegation_status,
-    cli::{AmountArgs, TargetArgs, TransferUsernameArgs, UndelegateArgs, WaitArgs},
+    cli::{
+        AmountArgs, ClaimArgs, ClaimUsernameArgs, DelegateArgs, DepositArgs, RefundArgs,
+        TargetArgs, TransferUsernameArgs, UndelegateArgs, WaitArgs,
+    },
     constants::{
         DEFAULT_OWNER_WAIT_INTERVAL_SECONDS, DEFAULT_OWNER_WAIT_TIMEOUT_SECONDS,
         USERNAME_INIT_WAIT_ATTEMPTS, USERNAME_INIT_WAIT_INTERVAL_MS,
     },
     context::{parse_pubkey, resolve_target},
     pda::{
-        build_create_permission_ix, build_delegate_deposit_ix, build_delegate_username_deposit_ix,
-        build_initialize_deposit_ix, build_initialize_username_deposit_ix, build_modify_balance_ix,
+        build_claim_username_deposit_to_wallet_ix, build_create_permission_ix,
+        build_delegate_deposit_ix, build_delegate_username_deposit_ix, build_initialize_deposit_ix,
+        build_initialize_username_deposit_ix, build_modify_balance_ix,
         build_transfer_to_username_deposit_ix, build_undelegate_deposit_ix,
         build_undelegate_username_deposit_ix, delegation_program_id, find_deposit_pda,
         find_permission_pda, find_username_deposit_pda, permission_program_id, program_id,
@@ -23,7 +28,8 @@ use crate::{
     },
     solana_ops::{
         account_owner_is, close_wsol_ata, ensure_ata_exists, fetch_deposit_amount,
-        fetch_deposit_amount_allow_not_found, fetch_username_deposit_amount,
+        fetch_deposit_amount_allow_not_found, fetch_mint_token_program,
+        fetch_transfer_deposit_amount, fetch_username_deposit_amount,
         fetch_username_deposit_amount_allow_not_found, get_account_opt,
         get_account_opt_allow_not_found, print_signature, send_ix, send_ix_with_opts,
         wait_for_account_exists, wait_for_owner, wrap_sol_to_wsol,
@@ -154,9 +160,9 @@ pub(crate) fn cmd_display(ctx: &AppContext, args: &TargetArgs) -> Result<()> {
     Ok(())
 }
 
-pub(crate) fn cmd_delegate(ctx: &mut AppContext, args: &TargetArgs) -> Result<()> {
+pub(crate) fn cmd_delegate(ctx: &mut AppContext, args: &DelegateArgs) -> Result<()> {
     debug!("running command: delegate with args {:?}", args);
-    let target = resolve_target(args, ctx.signer_pubkey)?;
+    let target = resolve_target(&args.target, ctx.signer_pubkey)?;
     let signature = match target {
         Target::Deposit {
             user,
@@ -178,8 +184,15 @@ pub(crate) fn cmd_delegate(ctx: &mut AppContext, args: &TargetArgs) -> Result<()
             mint,
             deposit,
         } => {
+            // without the owner's session only allowlisted validators are accepted
+            let session = args
+                .session
+                .as_ref()
+                .map(|s| parse_pubkey(s, "session"))
+                .transpose()?;
             let ix = build_delegate_username_deposit_ix(
                 ctx.signer_pubkey,
+                session,
                 &username,
                 mint,
                 deposit,
@@ -487,8 +500,10 @@ pub(crate) fn cmd_transfer_username(
         &delegation_program_id(),
         ctx.commitment,
     )? {
+        // the recipient's bucket can only go to an allowlisted validator without their session
         let delegate_ix = build_delegate_username_deposit_ix(
             user,
+            None,
             &args.username,
             mint,
             destination,
@@ -509,3 +524,120 @@ pub(crate) fn cmd_transfer_username(
 
     print_signature(ctx.output, sig)
 }
+
+pub(crate) fn cmd_claim_username(ctx: &AppContext, args: &ClaimUsernameArgs) -> Result<()> {
+    debug!("running command: claim_username with args {:?}", args);
+    validate_username(&args.username)?;
+
+    let mint = parse_pubkey(&args.mint, "mint")?;
+    let deposit = find_username_deposit_pda(&args.username, &mint);
+    if !account_owner_is(&ctx.base_client, &deposit, &program_id(), ctx.commitment)? {
+        bail!(
+            "username deposit {} is delegated or missing; run `loyal undelegate` first",
+            deposit
+        );
+    }
+    let amount = match args.amount {
+        Some(amount) => amount,
+        None => fetch_username_deposit_amount(&ctx.base_client, &deposit, ctx.commitment)?
+            .ok_or_else(|| anyhow!("no username deposit for @{} at {}", args.username, deposit))?,
+    };
+
+    // the program unwraps native SOL itself, so no close_wsol_ata here
+    let session = transfer_client::find_session_pda(&ctx.signer_pubkey);
+    let token_program = fetch_mint_token_program(&ctx.base_client, &mint, ctx.commitment)?;
+    let ix = build_claim_username_deposit_to_wallet_ix(
+        ctx.signer_pubkey,
+        session,
+        mint,
+        deposit,
+        amount,
+        token_program,
+    );
+    let sig = send_ix_with_opts(
+        &ctx.base_client,
+        &ctx.signer,
+        ix,
+        ctx.simulate,
+        ctx.simulate_only,
+    )?;
+
+    print_signature(ctx.output, sig)
+}
+
+pub(crate) fn cmd_deposit(ctx: &AppContext, args: &DepositArgs) -> Result<()> {
+    debug!("running command: deposit with args {:?}", args);
+    validate_username(&args.username)?;
+
+    let user = ctx.signer_pubkey;
+    let ix = transfer_client::deposit_for_username_ix(user, user, &args.username, args.amount);
+    let sig = send_ix_with_opts(
+        &ctx.base_client,
+        &ctx.signer,
+        ix,
+        ctx.simulate,
+        ctx.simulate_only,
+    )?;
+
+    print_signature(ctx.output, sig)
+}
+
+pub(crate) fn cmd_refund(ctx: &AppContext, args: &RefundArgs) -> Result<()> {
+    debug!("running command: refund with args {:?}", args);
+    validate_username(&args.username)?;
+
+    let user = ctx.signer_pubkey;
+    let deposit = transfer_client::find_deposit_pda(&user, &args.username);
+    let amount = match args.amount {
+        Some(amount) => amount,
+        None => fetch_transfer_deposit_amount(&ctx.base_client, &deposit, ctx.commitment)?
+            .ok_or_else(|| anyhow!("no deposit for @{} at {}", args.username, deposit))?,
+    };
+
+    let ix = transfer_client::refund_deposit_ix(user, deposit, amount);
+    let sig = send_ix_with_opts(
+        &ctx.base_client,
+        &ctx.signer,
+        ix,
+        ctx.simulate,
+        ctx.simulate_only,
+    )?;
+
+    print_signature(ctx.output, sig)
+}
+
+pub(crate) fn cmd_claim(ctx: &AppContext, args: &ClaimArgs) -> Result<()> {
+    debug!("running command: claim with args {:?}", args);
+    validate_username(&args.username)?;
+
+    let depositor = parse_pubkey(&args.depositor, "depositor")?;
+    let deposit = transfer_client::find_deposit_pda(&depositor, &args.username);
+    let amount = match args.amount {
+        Some(amount) => amount,
+        None => fetch_transfer_deposit_amount(&ctx.base_client, &deposit, ctx.commitment)?
+            .ok_or_else(|| anyhow!("no deposit for @{} at {}", args.username, deposit))?,
+    };
+
+    let session = transfer_client::find_session_pda(&ctx.signer_pubkey);
+    let Some(session_account) = get_account_opt(&ctx.base_client, &session, ctx.commitment)? else {
+        bail!(
+            "no Telegram session {} for {}; verify your username first",
+            session,
+            ctx.signer_pubkey
+        );
+    };
+    let relayer = transfer_client::decode_session(&session_account.data)
+        .map_err(|err| anyhow!("failed to decode Telegram session {session}: {err}"))?
+        .relayer;
+
+    let ix = transfer_client::claim_deposit_ix(ctx.signer_pubkey, deposit, amount, relayer);
+    let sig = send_ix_with_opts(
+        &ctx.base_client,
+        &ctx.signer,
+        ix,
+        ctx.simulate,
+        ctx.simulate_only,
+    )?;
+
+    print_signature(ctx.output, sig)
+}
@@ -25,6 +25,7 @@ pub const IX_DELEGATE_USERNAME_DEPOSIT: [u8; 8] = [26, 82, 4, 176, 221, 64, 84,
 pub const IX_UNDELEGATE: [u8; 8] = [131, 148, 180, 198, 91, 104, 42, 238];
 pub const IX_UNDELEGATE_USERNAME_DEPOSIT: [u8; 8] = [169, 131, 184, 97, 218, 190, 134, 4];
 pub const IX_TRANSFER_TO_USERNAME_DEPOSIT: [u8; 8] = [224, 228, 188, 234, 232, 153, 75, 96];
+pub const IX_CLAIM_USERNAME_DEPOSIT_TO_WALLET: [u8; 8] = [204, 38, 200, 213, 35, 149, 147, 133];
 
 pub const DEPOSIT_DISCRIMINATOR: [u8; 8] = [148, 146, 121, 66, 207, 173, 21, 227];
 pub const USERNAME_DEPOSIT_DISCRIMINATOR: [u8; 8] = [242, 23, 53, 35, 55, 192, 177, 246];
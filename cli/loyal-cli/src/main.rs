@@ -12,8 +12,8 @@ mod types;
 
 use cli::{Cli, Command};
 use commands::{
-    cmd_delegate, cmd_display, cmd_shield, cmd_transfer_username, cmd_undelegate, cmd_unshield,
-    cmd_wait_state,
+    cmd_claim, cmd_claim_username, cmd_delegate, cmd_deposit, cmd_display, cmd_refund, cmd_shield,
+    cmd_transfer_username, cmd_undelegate, cmd_unshield, cmd_wait_state,
 };
 use context::{build_context, init_logging};
 
@@ -31,5 +31,9 @@ fn main() -> Result<()> {
         Command::Shield(args) => cmd_shield(&mut ctx, args),
         Command::Unshield(args) => cmd_unshield(&mut ctx, args),
         Command::TransferUsername(args) => cmd_transfer_username(&mut ctx, args),
+        Command::ClaimUsername(args) => cmd_claim_username(&ctx, args),
+        Command::Deposit(args) => cmd_deposit(&ctx, args),
+        Command::Refund(args) => cmd_refund(&ctx, args),
+        Command::Claim(args) => cmd_claim(&ctx, args),
     }
 }
@@ -8,10 +8,11 @@ use spl_associated_token_account_client::{
 };
 
 use crate::constants::{
-    DELEGATION_PROGRAM_ID_STR, IX_CREATE_PERMISSION, IX_DELEGATE, IX_DELEGATE_USERNAME_DEPOSIT,
-    IX_INITIALIZE_DEPOSIT, IX_INITIALIZE_USERNAME_DEPOSIT, IX_MODIFY_BALANCE,
-    IX_TRANSFER_TO_USERNAME_DEPOSIT, IX_UNDELEGATE, IX_UNDELEGATE_USERNAME_DEPOSIT,
-    MAGIC_CONTEXT_ID_STR, MAGIC_PROGRAM_ID_STR, PERMISSION_PROGRAM_ID_STR, PROGRAM_ID_STR,
+    DELEGATION_PROGRAM_ID_STR, IX_CLAIM_USERNAME_DEPOSIT_TO_WALLET, IX_CREATE_PERMISSION,
+    IX_DELEGATE, IX_DELEGATE_USERNAME_DEPOSIT, IX_INITIALIZE_DEPOSIT,
+    IX_INITIALIZE_USERNAME_DEPOSIT, IX_MODIFY_BALANCE, IX_TRANSFER_TO_USERNAME_DEPOSIT,
+    IX_UNDELEGATE, IX_UNDELEGATE_USERNAME_DEPOSIT, MAGIC_CONTEXT_ID_STR, MAGIC_PROGRAM_ID_STR,
+    PERMISSION_PROGRAM_ID_STR, PROGRAM_ID_STR,
 };
 
 pub(crate) fn build_initialize_deposit_ix(
@@ -93,6 +94,42 @@ pub(crate) fn build_modify_balance_ix(
     }
 }
 
+pub(crate) fn build_claim_username_deposit_to_wallet_ix(
+    user: Pubkey,
+    session: Pubkey,
+    mint: Pubkey,
+    username_deposit: Pubkey,
+    amount: u64,
+    token_program: Pubkey,
+) -> Instruction {
+    let vault = find_vault_pda(&mint);
+    let user_token_account =
+        get_associated_token_address_with_program_id(&user, &mint, &token_program);
+    let vault_token_account =
+        get_associated_token_address_with_program_id(&vault, &mint, &token_program);
+
+    let mut data = IX_CLAIM_USERNAME_DEPOSIT_TO_WALLET.to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+
+    Instruction {
+        program_id: program_id(),
+        accounts: vec![
+            AccountMeta::new(user, true),
+            AccountMeta::new(user, true),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(username_deposit, false),
+            AccountMeta::new_readonly(session, false),
+            AccountMeta::new(user_token_account, false),
+            AccountMeta::new(vault_token_account, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(token_program, false),
+            AccountMeta::new_readonly(associated_token_program::id(), false),
+            AccountMeta::new_readonly(system_program_id(), false),
+        ],
+        data,
+    }
+}
+
 pub(crate) fn build_create_permission_ix(
     payer: Pubkey,
     user: Pubkey,
@@ -147,6 +184,7 @@ pub(crate) fn build_delegate_deposit_ix(
 
 pub(crate) fn build_delegate_username_deposit_ix(
     payer: Pubkey,
+    session: Option<Pubkey>,
     username: &str,
     mint: Pubkey,
     deposit: Pubkey,
@@ -165,6 +203,8 @@ pub(crate) fn build_delegate_username_deposit_ix(
         accounts: vec![
             AccountMeta::new(payer, true),
             AccountMeta::new_readonly(validator, false),
+            // Anchor optional account sentinel for `session: None`.
+            AccountMeta::new_readonly(session.unwrap_or_else(program_id), false),
             AccountMeta::new(buffer, false),
             AccountMeta::new(delegation_record, false),
             AccountMeta::new(delegation_metadata, false),
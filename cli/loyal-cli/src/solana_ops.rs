@@ -1,4 +1,4 @@
-use anyhow::{bail, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use log::debug;
 use serde_json::json;
 use solana_client::rpc_client::RpcClient;
@@ -21,6 +21,7 @@ use std::{
     thread::sleep,
     time::{Duration, Instant},
 };
+use telegram_transfer::client as transfer_client;
 
 use crate::{
     cli::OutputFormat,
@@ -108,6 +109,22 @@ pub(crate) fn account_owner_is(
         .unwrap_or(false))
 }
 
+/// Token program owning `mint`, SPL Token or Token-2022.
+pub(crate) fn fetch_mint_token_program(
+    client: &RpcClient,
+    mint: &Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<Pubkey> {
+    let Some(account) = get_account_opt(client, mint, commitment)? else {
+        bail!("mint {} not found", mint);
+    };
+    debug!(
+        "fetch_mint_token_program result: mint={}, owner={}",
+        mint, account.owner
+    );
+    Ok(account.owner)
+}
+
 pub(crate) fn get_account_opt(
     client: &RpcClient,
     address: &Pubkey,
@@ -185,6 +202,32 @@ pub(crate) fn fetch_deposit_amount_allow_not_found(
     Ok(Some(parsed.amount))
 }
 
+/// Amount escrowed in a telegram-transfer `Deposit`, or `None` if it does not exist.
+pub(crate) fn fetch_transfer_deposit_amount(
+    client: &RpcClient,
+    address: &Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<Option<u64>> {
+    debug!("fetch_transfer_deposit_amount: account={address}");
+    let Some(account) = get_account_opt(client, address, commitment)? else {
+        return Ok(None);
+    };
+    if account.owner != telegram_transfer::ID {
+        bail!(
+            "account {} is not owned by telegram-transfer (owner {})",
+            address,
+            account.owner
+        );
+    }
+    let parsed = transfer_client::decode_deposit(&account.data)
+        .map_err(|err| anyhow!("failed to decode deposit {address}: {err}"))?;
+    debug!(
+        "fetch_transfer_deposit_amount result: account={}, amount={}",
+        address, parsed.amount
+    );
+    Ok(Some(parsed.amount))
+}
+
 pub(crate) fn fetch_username_deposit_amount(
     client: &RpcClient,
     address: &Pubkey,
@@ -0,0 +1,44 @@
+//! Token mints `telegram-transfer` and `telegram-private-transfer` accept into
+//! their shared per-mint vaults.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
+
+/// Whether a shared per-mint vault can safely hold tokens of `mint`.
+///
+/// SPL Token mints always can. Token-2022 mints only if none of their extensions
+/// can hurt the vault, e.g. a permanent delegate that could drain it, confidential
+/// balances it cannot account for, or transfer hooks whose extra accounts are not
+/// passed.
+pub fn is_supported_mint(mint: &AccountInfo) -> Result<bool> {
+    if *mint.owner != anchor_spl::token_2022::ID {
+        return Ok(true);
+    }
+
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<Mint>::unpack(&data)?;
+    Ok(state
+        .get_extension_types()?
+        .into_iter()
+        .all(is_supported_extension))
+}
+
+/// Token-2022 mint extensions that leave a vault's balance to the vault alone.
+pub fn is_supported_extension(extension: ExtensionType) -> bool {
+    matches!(
+        extension,
+        ExtensionType::TransferFeeConfig
+            | ExtensionType::MintCloseAuthority
+            | ExtensionType::InterestBearingConfig
+            | ExtensionType::ScaledUiAmount
+            | ExtensionType::MetadataPointer
+            | ExtensionType::TokenMetadata
+            | ExtensionType::GroupPointer
+            | ExtensionType::TokenGroup
+            | ExtensionType::GroupMemberPointer
+            | ExtensionType::TokenGroupMember
+    )
+}
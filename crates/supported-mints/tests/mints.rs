@@ -0,0 +1,75 @@
+use anchor_lang::prelude::{AccountInfo, Pubkey};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    permanent_delegate::PermanentDelegate, transfer_fee::TransferFeeConfig,
+    BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Mint;
+use supported_mints::is_supported_mint;
+
+/// Initialized Token-2022 mint data with default-valued `extensions`.
+fn mint_data(extensions: &[ExtensionType]) -> Vec<u8> {
+    let len = ExtensionType::try_calculate_account_len::<Mint>(extensions).unwrap();
+    let mut data = vec![0; len];
+    let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+    for extension in extensions {
+        match extension {
+            ExtensionType::TransferFeeConfig => {
+                state.init_extension::<TransferFeeConfig>(true).unwrap();
+            }
+            ExtensionType::PermanentDelegate => {
+                state.init_extension::<PermanentDelegate>(true).unwrap();
+            }
+            _ => unimplemented!(),
+        }
+    }
+    state.base = Mint {
+        decimals: 6,
+        is_initialized: true,
+        ..Mint::default()
+    };
+    state.pack_base();
+    state.init_account_type().unwrap();
+    data
+}
+
+fn supported(owner: Pubkey, mut data: Vec<u8>) -> bool {
+    let key = Pubkey::new_unique();
+    let mut lamports = 0;
+    let mint = AccountInfo::new(
+        &key,
+        false,
+        false,
+        &mut lamports,
+        &mut data,
+        &owner,
+        false,
+        0,
+    );
+    is_supported_mint(&mint).unwrap()
+}
+
+#[test]
+fn accepts_token_2022_mints_with_harmless_extensions() {
+    let token_2022 = anchor_spl::token_2022::ID;
+    assert!(supported(token_2022, mint_data(&[])));
+    assert!(supported(
+        token_2022,
+        mint_data(&[ExtensionType::TransferFeeConfig])
+    ));
+}
+
+#[test]
+fn rejects_token_2022_mints_with_a_permanent_delegate() {
+    assert!(!supported(
+        anchor_spl::token_2022::ID,
+        mint_data(&[
+            ExtensionType::TransferFeeConfig,
+            ExtensionType::PermanentDelegate
+        ])
+    ));
+}
+
+#[test]
+fn accepts_spl_token_mints_without_reading_them() {
+    assert!(supported(anchor_spl::token::ID, Vec::new()));
+}
@@ -1,8 +1,13 @@
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
-use anchor_spl::token::{Mint, Token, TokenAccount};
-use ephemeral_rollups_sdk::access_control::instructions::CreatePermissionCpiBuilder;
+use anchor_spl::token::spl_token::native_mint;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use ephemeral_rollups_sdk::access_control::instructions::{
+    ClosePermissionCpiBuilder, CreatePermissionCpiBuilder,
+};
+use ephemeral_rollups_sdk::access_control::structs::Permission;
 use ephemeral_rollups_sdk::anchor::{commit, delegate, ephemeral};
+use ephemeral_rollups_sdk::consts::PERMISSION_PROGRAM_ID;
 use ephemeral_rollups_sdk::cpi::DelegateConfig;
 use ephemeral_rollups_sdk::ephem::commit_and_undelegate_accounts;
 use session_keys::{session_auth_or, Session, SessionError, SessionToken};
@@ -18,10 +23,23 @@ pub const VAULT_PDA_SEED: &[u8] = b"vault";
 const MIN_USERNAME_LEN: usize = 5;
 const MAX_USERNAME_LEN: usize = 32;
 
+/// Validators anyone may delegate a username deposit to, so senders can move a
+/// recipient's bucket into the rollup. Any other validator needs the owner's session.
+///
+/// The local ephemeral validator is left out on purpose: its keypair is public.
+const ALLOWED_VALIDATORS: [Pubkey; 2] = [
+    // TEE devnet
+    pubkey!("FnE6VJT5QNZdedZPnCoLsARgBwoE6DeJNjBs2H1gySXA"),
+    // TEE mainnet
+    pubkey!("MTEWGuqxUpYZGFJQcp8tLN7x5v9BSeoFHYWQQ3n3xzo"),
+];
+
 #[ephemeral]
 #[program]
 pub mod telegram_private_transfer {
-    use anchor_spl::token::{transfer_checked, TransferChecked};
+    use anchor_spl::token_interface::{
+        close_account, transfer_checked, CloseAccount, TransferChecked,
+    };
     use ephemeral_rollups_sdk::access_control::structs::{
         Member, MembersArgs, ACCOUNT_SIGNATURES_FLAG, AUTHORITY_FLAG, TX_BALANCES_FLAG,
         TX_LOGS_FLAG, TX_MESSAGE_FLAG,
@@ -38,10 +56,13 @@ pub mod telegram_private_transfer {
 
         // Only initialize if account is fresh (uninitialized)
         if deposit.user == Pubkey::default() {
+            validate_mint(&ctx.accounts.token_mint)?;
             deposit.set_inner(Deposit {
                 user: ctx.accounts.user.key(),
                 token_mint: ctx.accounts.token_mint.key(),
                 amount: 0,
+                payer: Some(ctx.accounts.payer.key()),
+                permission_payer: None,
             });
         }
 
@@ -58,9 +79,12 @@ pub mod telegram_private_transfer {
 
         // Only initialize if account is fresh (uninitialized)
         if deposit.token_mint == Pubkey::default() {
+            validate_mint(&ctx.accounts.token_mint)?;
             deposit.token_mint = ctx.accounts.token_mint.key();
             deposit.username = username.clone();
             deposit.amount = 0;
+            deposit.payer = Some(ctx.accounts.payer.key());
+            deposit.permission_payer = None;
         }
 
         Ok(())
@@ -70,10 +94,14 @@ pub mod telegram_private_transfer {
     ///
     /// If `args.increase` is true, tokens are transferred from the user's token account to the deposit account.
     /// If false, tokens are transferred from the deposit account back to the user's token account.
+    ///
+    /// Deposits are credited with what the vault actually received, net of any Token-2022 transfer fee.
     pub fn modify_balance(ctx: Context<ModifyDeposit>, args: ModifyDepositArgs) -> Result<()> {
         let deposit = &mut ctx.accounts.deposit;
 
         if args.increase {
+            validate_mint(&ctx.accounts.token_mint)?;
+            let vault_balance_before = ctx.accounts.vault_token_account.amount;
             transfer_checked(
                 CpiContext::new(
                     ctx.accounts.token_program.to_account_info(),
@@ -87,9 +115,21 @@ pub mod telegram_private_transfer {
                 args.amount,
                 ctx.accounts.token_mint.decimals,
             )?;
+            ctx.accounts.vault_token_account.reload()?;
+            let received = ctx
+                .accounts
+                .vault_token_account
+                .amount
+                .checked_sub(vault_balance_before)
+                .ok_or(ErrorCode::Overflow)?;
             deposit.amount = deposit
                 .amount
-                .checked_add(args.amount)
+                .checked_add(received)
+                .ok_or(ErrorCode::Overflow)?;
+            let vault = &mut ctx.accounts.vault;
+            vault.total_shielded = vault
+                .total_shielded
+                .checked_add(received)
                 .ok_or(ErrorCode::Overflow)?;
         } else {
             let seeds = [
@@ -116,6 +156,11 @@ pub mod telegram_private_transfer {
                 .amount
                 .checked_sub(args.amount)
                 .ok_or(ErrorCode::InsufficientDeposit)?;
+            let vault = &mut ctx.accounts.vault;
+            vault.total_shielded = vault
+                .total_shielded
+                .checked_sub(args.amount)
+                .ok_or(ErrorCode::InsufficientVault)?;
         }
 
         Ok(())
@@ -130,7 +175,10 @@ pub mod telegram_private_transfer {
         let destination_deposit = &mut ctx.accounts.destination_deposit;
         let session = &ctx.accounts.session;
 
-        require!(session.verified, ErrorCode::NotVerified);
+        require!(
+            session.is_verified(Clock::get()?.unix_timestamp as u64),
+            ErrorCode::NotVerified
+        );
         require!(
             session.username == source_username_deposit.username,
             ErrorCode::InvalidUsername
@@ -152,6 +200,97 @@ pub mod telegram_private_transfer {
         Ok(())
     }
 
+    /// Claim tokens from a username deposit straight to the recipient's token account.
+    ///
+    /// For the native mint the recipient's wSOL account is closed afterwards, so the
+    /// claim (and any wSOL already held there) arrives as SOL.
+    pub fn claim_username_deposit_to_wallet(
+        ctx: Context<ClaimUsernameDepositToWallet>,
+        amount: u64,
+    ) -> Result<()> {
+        let username_deposit = &mut ctx.accounts.username_deposit;
+        let vault = &mut ctx.accounts.vault;
+
+        username_deposit.amount = username_deposit
+            .amount
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientDeposit)?;
+        vault.total_shielded = vault
+            .total_shielded
+            .checked_sub(amount)
+            .ok_or(ErrorCode::InsufficientVault)?;
+
+        let seeds = [
+            VAULT_PDA_SEED,
+            &ctx.accounts.token_mint.key().to_bytes(),
+            &[ctx.bumps.vault],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        transfer_checked(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.vault_token_account.to_account_info(),
+                    mint: ctx.accounts.token_mint.to_account_info(),
+                    to: ctx.accounts.user_token_account.to_account_info(),
+                    authority: ctx.accounts.vault.to_account_info(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            ctx.accounts.token_mint.decimals,
+        )?;
+
+        if ctx.accounts.token_mint.key() == native_mint::ID {
+            close_account(CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: ctx.accounts.user_token_account.to_account_info(),
+                    destination: ctx.accounts.user.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
+                },
+            ))?;
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the vault token account covers every deposit of its mint.
+    ///
+    /// Permissionless; emits `VaultAudited` and fails when the vault is insolvent.
+    pub fn audit_vault(ctx: Context<AuditVault>) -> Result<()> {
+        let balance = ctx.accounts.vault_token_account.amount;
+        let liabilities = ctx.accounts.vault.total_shielded;
+        let solvent = balance >= liabilities;
+
+        emit!(VaultAudited {
+            token_mint: ctx.accounts.token_mint.key(),
+            balance,
+            liabilities,
+            solvent,
+        });
+        require!(solvent, ErrorCode::VaultInsolvent);
+        Ok(())
+    }
+
+    /// Grows a vault created before per-mint accounting to the current layout.
+    ///
+    /// Only the program upgrade authority can call this. What a legacy vault owes
+    /// is not tracked on-chain and its token balance says nothing about it, so the
+    /// authority attests `total_shielded`, the summed `Deposit` and `UsernameDeposit`
+    /// balances of the mint. Until migrated, the vault rejects balance changes.
+    pub fn migrate_vault(ctx: Context<MigrateVault>, total_shielded: u64) -> Result<()> {
+        let vault = &ctx.accounts.vault;
+        let migrated = Vault::try_from_legacy(&vault.try_borrow_data()?, total_shielded)?;
+        migrate_account(
+            &ctx.accounts.payer,
+            vault,
+            &ctx.accounts.system_program,
+            &migrated,
+            8 + Vault::INIT_SPACE,
+        )
+    }
+
     /// Transfers a specified amount from one user's deposit account to another's for the same token mint.
     ///
     /// Only updates the internal accounting; does not move actual tokens.
@@ -225,10 +364,10 @@ pub mod telegram_private_transfer {
             pubkey: user.key(),
             flags,
         }];
-        CreatePermissionCpiBuilder::new(&permission_program)
-            .permission(&permission)
+        CreatePermissionCpiBuilder::new(permission_program)
+            .permission(permission)
             .permissioned_account(&deposit.to_account_info())
-            .payer(&payer)
+            .payer(payer)
             .system_program(system_program)
             .args(MembersArgs {
                 members: Some(members),
@@ -239,6 +378,7 @@ pub mod telegram_private_transfer {
                 deposit.token_mint.as_ref(),
                 &[ctx.bumps.deposit],
             ]])?;
+        deposit.permission_payer = Some(payer.key());
 
         Ok(())
     }
@@ -255,7 +395,10 @@ pub mod telegram_private_transfer {
             system_program,
         } = ctx.accounts;
 
-        require!(session.verified, ErrorCode::NotVerified);
+        require!(
+            session.is_verified(Clock::get()?.unix_timestamp as u64),
+            ErrorCode::NotVerified
+        );
         require!(
             session.username == deposit.username,
             ErrorCode::InvalidUsername
@@ -275,10 +418,10 @@ pub mod telegram_private_transfer {
             pubkey: authority.key(),
             flags,
         }];
-        CreatePermissionCpiBuilder::new(&permission_program)
-            .permission(&permission)
+        CreatePermissionCpiBuilder::new(permission_program)
+            .permission(permission)
             .permissioned_account(&deposit.to_account_info())
-            .payer(&payer)
+            .payer(payer)
             .system_program(system_program)
             .args(MembersArgs {
                 members: Some(members),
@@ -289,6 +432,7 @@ pub mod telegram_private_transfer {
                 deposit.token_mint.as_ref(),
                 &[ctx.bumps.deposit],
             ]])?;
+        deposit.permission_payer = Some(payer.key());
 
         Ok(())
     }
@@ -310,23 +454,22 @@ pub mod telegram_private_transfer {
     }
 
     /// Delegates the username-based deposit account to the ephemeral rollups delegate program.
+    ///
+    /// The verified owner of the username may pick any validator; without their
+    /// session the validator must be one of `ALLOWED_VALIDATORS`.
     pub fn delegate_username_deposit(
         ctx: Context<DelegateUsernameDeposit>,
         username: String,
         token_mint: Pubkey,
     ) -> Result<()> {
         validate_username(&username)?;
-        // require!(ctx.accounts.session.verified, ErrorCode::NotVerified);
-        // require!(
-        //     ctx.accounts.session.username == username,
-        //     ErrorCode::InvalidUsername
-        // );
-        // require_keys_eq!(
-        //     ctx.accounts.session.user_wallet,
-        //     ctx.accounts.payer.key(),
-        //     ErrorCode::Unauthorized
-        // );
         let validator = ctx.accounts.validator.as_ref().map(|v| v.key());
+        if ctx.accounts.session.is_none() {
+            require!(
+                validator.is_some_and(|v| ALLOWED_VALIDATORS.contains(&v)),
+                ErrorCode::InvalidValidator
+            );
+        }
         ctx.accounts.delegate_deposit(
             &ctx.accounts.payer,
             &[
@@ -366,7 +509,12 @@ pub mod telegram_private_transfer {
         token_mint: Pubkey,
     ) -> Result<()> {
         validate_username(&username)?;
-        require!(ctx.accounts.session.verified, ErrorCode::NotVerified);
+        require!(
+            ctx.accounts
+                .session
+                .is_verified(Clock::get()?.unix_timestamp as u64),
+            ErrorCode::NotVerified
+        );
         require!(
             ctx.accounts.session.username == username,
             ErrorCode::InvalidUsername
@@ -398,6 +546,121 @@ pub mod telegram_private_transfer {
         )?;
         Ok(())
     }
+
+    /// Closes an empty deposit and its permission, returning each one's rent to whoever paid for it.
+    ///
+    /// The permission program refunds only a signer, so the permission's rent passes through
+    /// the owner on its way to the permission's payer. A delegated deposit is owned by the
+    /// delegation program and fails to load here, so undelegate it first.
+    pub fn close_deposit(ctx: Context<CloseDeposit>) -> Result<()> {
+        let CloseDeposit {
+            user,
+            deposit,
+            permission,
+            permission_rent_receiver,
+            permission_program,
+            system_program,
+            ..
+        } = ctx.accounts;
+
+        require!(deposit.amount == 0, ErrorCode::DepositNotEmpty);
+
+        if !permission.data_is_empty() {
+            let owner_lamports = user.lamports();
+            ClosePermissionCpiBuilder::new(permission_program)
+                .permission(permission)
+                .permissioned_account(&deposit.to_account_info(), true)
+                .payer(user)
+                .invoke_signed(&[&[
+                    DEPOSIT_PDA_SEED,
+                    user.key().as_ref(),
+                    deposit.token_mint.as_ref(),
+                    &[ctx.bumps.deposit],
+                ]])?;
+            forward_permission_rent(
+                user,
+                permission_rent_receiver,
+                system_program,
+                user.lamports().saturating_sub(owner_lamports),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Closes an empty username deposit and its permission, returning each one's rent to whoever paid for it.
+    ///
+    /// Only the verified owner of the username can close it. As in `close_deposit`, the
+    /// permission's rent passes through them on its way to the permission's payer.
+    pub fn close_username_deposit(ctx: Context<CloseUsernameDeposit>) -> Result<()> {
+        let CloseUsernameDeposit {
+            authority,
+            deposit,
+            permission,
+            permission_rent_receiver,
+            permission_program,
+            system_program,
+            ..
+        } = ctx.accounts;
+
+        require!(deposit.amount == 0, ErrorCode::DepositNotEmpty);
+
+        if !permission.data_is_empty() {
+            let owner_lamports = authority.lamports();
+            ClosePermissionCpiBuilder::new(permission_program)
+                .permission(permission)
+                .permissioned_account(&deposit.to_account_info(), true)
+                .payer(authority)
+                .invoke_signed(&[&[
+                    USERNAME_DEPOSIT_PDA_SEED,
+                    deposit.username.as_bytes(),
+                    deposit.token_mint.as_ref(),
+                    &[ctx.bumps.deposit],
+                ]])?;
+            forward_permission_rent(
+                authority,
+                permission_rent_receiver,
+                system_program,
+                authority.lamports().saturating_sub(owner_lamports),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Grows a deposit created before the rent payer was recorded to the current layout.
+    ///
+    /// Permissionless. Both payers are left as `None`, so rent goes back to the owner on close.
+    pub fn migrate_deposit(ctx: Context<MigrateDeposit>) -> Result<()> {
+        let deposit = &ctx.accounts.deposit;
+        let migrated = Deposit::try_from_legacy(&deposit.try_borrow_data()?)?;
+        migrate_account(
+            &ctx.accounts.payer,
+            deposit,
+            &ctx.accounts.system_program,
+            &migrated,
+            8 + Deposit::INIT_SPACE,
+        )
+    }
+
+    /// Grows a username deposit created before the rent payer was recorded to the current layout.
+    ///
+    /// Permissionless. Both payers are left as `None`, so rent goes to the verified owner on close.
+    pub fn migrate_username_deposit(
+        ctx: Context<MigrateUsernameDeposit>,
+        username: String,
+    ) -> Result<()> {
+        validate_username(&username)?;
+        let deposit = &ctx.accounts.deposit;
+        let migrated = UsernameDeposit::try_from_legacy(&deposit.try_borrow_data()?)?;
+        migrate_account(
+            &ctx.accounts.payer,
+            deposit,
+            &ctx.accounts.system_program,
+            &migrated,
+            8 + UsernameDeposit::INIT_SPACE,
+        )
+    }
 }
 
 // ---------------- Accounts ----------------
@@ -415,8 +678,8 @@ pub struct InitializeDeposit<'info> {
         bump
     )]
     pub deposit: Account<'info, Deposit>,
-    pub token_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
@@ -437,8 +700,8 @@ pub struct InitializeUsernameDeposit<'info> {
         bump
     )]
     pub deposit: Account<'info, UsernameDeposit>,
-    pub token_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
@@ -474,17 +737,19 @@ pub struct ModifyDeposit<'info> {
         payer = payer,
         associated_token::mint = token_mint,
         associated_token::authority = user,
+        associated_token::token_program = token_program,
     )]
-    pub user_token_account: Account<'info, TokenAccount>,
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
     #[account(
         init_if_needed,
         payer = payer,
         associated_token::mint = token_mint,
         associated_token::authority = vault,
+        associated_token::token_program = token_program,
     )]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    pub token_mint: Account<'info, Mint>,
-    pub token_program: Program<'info, Token>,
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
 }
@@ -512,14 +777,60 @@ pub struct ClaimUsernameDepositToDeposit<'info> {
         has_one = token_mint,
     )]
     pub destination_deposit: Account<'info, Deposit>,
-    pub token_mint: Account<'info, Mint>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
     #[account(
         constraint = session.user_wallet == destination_deposit.user @ ErrorCode::InvalidRecipient,
-        constraint = session.verified @ ErrorCode::NotVerified,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified,
         constraint = session.username == source_username_deposit.username @ ErrorCode::InvalidUsername,
     )]
     pub session: Account<'info, TelegramSession>,
-    pub token_program: Program<'info, Token>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimUsernameDepositToWallet<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [VAULT_PDA_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        mut,
+        seeds = [USERNAME_DEPOSIT_PDA_SEED, username_deposit.username.as_bytes(), username_deposit.token_mint.as_ref()],
+        bump,
+        has_one = token_mint,
+    )]
+    pub username_deposit: Account<'info, UsernameDeposit>,
+    #[account(
+        constraint = session.user_wallet == user.key() @ ErrorCode::InvalidRecipient,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified,
+        constraint = session.username == username_deposit.username @ ErrorCode::InvalidUsername,
+    )]
+    pub session: Account<'info, TelegramSession>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = token_mint,
+        associated_token::authority = user,
+        associated_token::token_program = token_program,
+    )]
+    pub user_token_account: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        associated_token::mint = token_mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts, Session)]
@@ -557,7 +868,7 @@ pub struct TransferDeposit<'info> {
         has_one = token_mint,
     )]
     pub destination_deposit: Account<'info, Deposit>,
-    pub token_mint: Account<'info, Mint>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
     pub system_program: Program<'info, System>,
 }
 
@@ -595,7 +906,169 @@ pub struct TransferToUsernameDeposit<'info> {
         has_one = token_mint,
     )]
     pub destination_deposit: Account<'info, UsernameDeposit>,
-    pub token_mint: Account<'info, Mint>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AuditVault<'info> {
+    #[account(
+        seeds = [VAULT_PDA_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub vault: Account<'info, Vault>,
+    #[account(
+        associated_token::mint = token_mint,
+        associated_token::authority = vault,
+        associated_token::token_program = token_program,
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
+    /// CHECK: Legacy vault, too short to deserialize as `Vault`
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [VAULT_PDA_SEED, token_mint.key().as_ref()],
+        bump,
+    )]
+    pub vault: UncheckedAccount<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::TelegramPrivateTransfer>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDeposit<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+    /// CHECK: Matched against the recorded payer
+    #[account(
+        mut,
+        address = deposit.payer.unwrap_or(user.key()) @ ErrorCode::InvalidRentReceiver,
+    )]
+    pub rent_receiver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [DEPOSIT_PDA_SEED, user.key().as_ref(), deposit.token_mint.as_ref()],
+        bump,
+        has_one = user,
+    )]
+    pub deposit: Account<'info, Deposit>,
+    /// CHECK: Permission PDA of the deposit
+    #[account(
+        mut,
+        address = Permission::find_pda(&deposit.key()).0 @ ErrorCode::InvalidPermission,
+    )]
+    pub permission: UncheckedAccount<'info>,
+    /// CHECK: Matched against the recorded permission payer
+    #[account(
+        mut,
+        address = deposit.permission_payer.unwrap_or(user.key()) @ ErrorCode::InvalidRentReceiver,
+    )]
+    pub permission_rent_receiver: UncheckedAccount<'info>,
+    /// CHECK: Matched against the permission program ID
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseUsernameDeposit<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// CHECK: Matched against the recorded payer
+    #[account(
+        mut,
+        address = deposit.payer.unwrap_or(authority.key()) @ ErrorCode::InvalidRentReceiver,
+    )]
+    pub rent_receiver: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [
+            USERNAME_DEPOSIT_PDA_SEED,
+            deposit.username.as_bytes(),
+            deposit.token_mint.as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, UsernameDeposit>,
+    #[account(
+        constraint = session.user_wallet == authority.key() @ ErrorCode::Unauthorized,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified,
+        constraint = session.username == deposit.username @ ErrorCode::InvalidUsername,
+    )]
+    pub session: Account<'info, TelegramSession>,
+    /// CHECK: Permission PDA of the deposit
+    #[account(
+        mut,
+        address = Permission::find_pda(&deposit.key()).0 @ ErrorCode::InvalidPermission,
+    )]
+    pub permission: UncheckedAccount<'info>,
+    /// CHECK: Matched against the recorded permission payer
+    #[account(
+        mut,
+        address = deposit.permission_payer.unwrap_or(authority.key()) @ ErrorCode::InvalidRentReceiver,
+    )]
+    pub permission_rent_receiver: UncheckedAccount<'info>,
+    /// CHECK: Matched against the permission program ID
+    #[account(address = PERMISSION_PROGRAM_ID)]
+    pub permission_program: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Only used as a seed
+    pub user: UncheckedAccount<'info>,
+    /// CHECK: Legacy deposit, too short to deserialize as `Deposit`
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [DEPOSIT_PDA_SEED, user.key().as_ref(), token_mint.key().as_ref()],
+        bump,
+    )]
+    pub deposit: UncheckedAccount<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(username: String)]
+pub struct MigrateUsernameDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: Legacy username deposit, too short to deserialize as `UsernameDeposit`
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [
+            USERNAME_DEPOSIT_PDA_SEED,
+            username.as_bytes(),
+            token_mint.key().as_ref()
+        ],
+        bump,
+    )]
+    pub deposit: UncheckedAccount<'info>,
+    pub token_mint: InterfaceAccount<'info, Mint>,
     pub system_program: Program<'info, System>,
 }
 
@@ -605,6 +1078,7 @@ pub struct CreatePermission<'info> {
     pub payer: Signer<'info>,
     pub user: Signer<'info>,
     #[account(
+        mut,
         seeds = [DEPOSIT_PDA_SEED, user.key().as_ref(), deposit.token_mint.as_ref()],
         bump
     )]
@@ -623,6 +1097,7 @@ pub struct CreateUsernamePermission<'info> {
     pub payer: Signer<'info>,
     pub authority: Signer<'info>,
     #[account(
+        mut,
         seeds = [
             USERNAME_DEPOSIT_PDA_SEED,
             deposit.username.as_bytes(),
@@ -633,7 +1108,7 @@ pub struct CreateUsernamePermission<'info> {
     pub deposit: Account<'info, UsernameDeposit>,
     #[account(
         constraint = session.user_wallet == authority.key() @ ErrorCode::Unauthorized,
-        constraint = session.verified @ ErrorCode::NotVerified,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified,
         constraint = session.username == deposit.username @ ErrorCode::InvalidUsername,
     )]
     pub session: Account<'info, TelegramSession>,
@@ -671,12 +1146,12 @@ pub struct DelegateUsernameDeposit<'info> {
     pub payer: Signer<'info>,
     /// CHECK: Checked by the delegate program
     pub validator: Option<AccountInfo<'info>>,
-    // #[account(
-    //     constraint = session.user_wallet == payer.key() @ ErrorCode::Unauthorized,
-    //     constraint = session.verified @ ErrorCode::NotVerified,
-    //     constraint = session.username == username @ ErrorCode::InvalidUsername,
-    // )]
-    // pub session: Account<'info, TelegramSession>,
+    #[account(
+        constraint = session.user_wallet == payer.key() @ ErrorCode::Unauthorized,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified,
+        constraint = session.username == username @ ErrorCode::InvalidUsername,
+    )]
+    pub session: Option<Account<'info, TelegramSession>>,
     /// CHECK: Checked by the delegate program
     #[account(
         mut,
@@ -715,7 +1190,7 @@ pub struct UndelegateUsernameDeposit<'info> {
     pub payer: Signer<'info>,
     #[account(
         constraint = session.user_wallet == payer.key() @ ErrorCode::Unauthorized,
-        constraint = session.verified @ ErrorCode::NotVerified,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified,
         constraint = session.username == username @ ErrorCode::InvalidUsername,
     )]
     pub session: Account<'info, TelegramSession>,
@@ -741,6 +1216,10 @@ pub struct Deposit {
     pub user: Pubkey,
     pub token_mint: Pubkey,
     pub amount: u64,
+    /// Who paid the rent; `None` for deposits migrated from the old layout.
+    pub payer: Option<Pubkey>,
+    /// Who paid for the permission; `None` if it was created before this was recorded.
+    pub permission_payer: Option<Pubkey>,
 }
 
 /// A deposit account for a telegram username and token mint.
@@ -753,14 +1232,120 @@ pub struct UsernameDeposit {
     pub username: String,
     pub token_mint: Pubkey,
     pub amount: u64,
+    /// Who paid the rent; `None` for deposits migrated from the old layout.
+    pub payer: Option<Pubkey>,
+    /// Who paid for the permission; `None` if it was created before this was recorded.
+    pub permission_payer: Option<Pubkey>,
 }
 
 /// A vault storing deposited tokens.
-/// Has a dummy field because Anchor requires it.
+///
+/// `_dummy` predates the accounting and is kept so legacy vaults only need to grow.
 #[account]
 #[derive(InitSpace)]
 pub struct Vault {
     _dummy: u8,
+    /// Sum of `Deposit.amount` and `UsernameDeposit.amount` for the vault's mint.
+    pub total_shielded: u64,
+}
+
+/// `Deposit` as stored before the rent payers were recorded.
+#[derive(AnchorDeserialize)]
+struct LegacyDeposit {
+    user: Pubkey,
+    token_mint: Pubkey,
+    amount: u64,
+}
+
+impl Deposit {
+    /// Reads a deposit account in the layout before the rent payers were recorded,
+    /// discriminator included. Both payers read back as `None`, so rent goes to the
+    /// owner on close. An account already sized for the current layout is rejected.
+    pub fn try_from_legacy(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() < 8 + Deposit::INIT_SPACE,
+            ErrorCode::DepositAlreadyMigrated
+        );
+        let mut rest = data
+            .strip_prefix(Deposit::DISCRIMINATOR)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch)?;
+        let legacy = LegacyDeposit::deserialize(&mut rest)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+
+        Ok(Self {
+            user: legacy.user,
+            token_mint: legacy.token_mint,
+            amount: legacy.amount,
+            payer: None,
+            permission_payer: None,
+        })
+    }
+}
+
+/// `UsernameDeposit` as stored before the rent payers were recorded.
+#[derive(AnchorDeserialize)]
+struct LegacyUsernameDeposit {
+    username: String,
+    token_mint: Pubkey,
+    amount: u64,
+}
+
+impl UsernameDeposit {
+    /// Reads a username deposit in the layout before the rent payers were recorded,
+    /// see `Deposit::try_from_legacy`.
+    pub fn try_from_legacy(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() < 8 + UsernameDeposit::INIT_SPACE,
+            ErrorCode::DepositAlreadyMigrated
+        );
+        let mut rest = data
+            .strip_prefix(UsernameDeposit::DISCRIMINATOR)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch)?;
+        let legacy = LegacyUsernameDeposit::deserialize(&mut rest)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+
+        Ok(Self {
+            username: legacy.username,
+            token_mint: legacy.token_mint,
+            amount: legacy.amount,
+            payer: None,
+            permission_payer: None,
+        })
+    }
+}
+
+impl Vault {
+    /// Reads a vault account in the layout before per-mint accounting, discriminator
+    /// included, booking the attested `total_shielded`. An account already sized
+    /// for the current layout is rejected.
+    pub fn try_from_legacy(data: &[u8], total_shielded: u64) -> Result<Self> {
+        require!(
+            data.len() < 8 + Vault::INIT_SPACE,
+            ErrorCode::VaultAlreadyMigrated
+        );
+        let mut rest = data
+            .strip_prefix(Vault::DISCRIMINATOR)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch)?;
+        let _dummy = u8::deserialize(&mut rest)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+
+        Ok(Self {
+            _dummy,
+            total_shielded,
+        })
+    }
+}
+
+// ---------------- Events ----------------
+
+#[event]
+pub struct VaultAudited {
+    pub token_mint: Pubkey,
+    /// Vault token account balance.
+    pub balance: u64,
+    /// Tracked shielded supply the balance must cover.
+    pub liabilities: u64,
+    pub solvent: bool,
 }
 
 // ---------------- Error Codes ----------------
@@ -790,6 +1375,92 @@ pub enum ErrorCode {
     InvalidRecipient,
     #[msg("Invalid Depositor")]
     InvalidDepositor,
+    #[msg("Invalid Validator")]
+    InvalidValidator,
+    #[msg("Unsupported Mint")]
+    UnsupportedMint,
+    #[msg("Vault Insolvent")]
+    VaultInsolvent,
+    #[msg("Vault Already Migrated")]
+    VaultAlreadyMigrated,
+    #[msg("Deposit Not Empty")]
+    DepositNotEmpty,
+    #[msg("Deposit Already Migrated")]
+    DepositAlreadyMigrated,
+    #[msg("Invalid Rent Receiver")]
+    InvalidRentReceiver,
+    #[msg("Invalid Permission")]
+    InvalidPermission,
+}
+
+/// Reallocates a program-owned account to `space`, topping up rent from `payer`.
+fn grow_account<'info>(
+    payer: &Signer<'info>,
+    account: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if shortfall > 0 {
+        anchor_lang::system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: account.to_account_info(),
+                },
+            ),
+            shortfall,
+        )?;
+    }
+    account.resize(space)?;
+    Ok(())
+}
+
+/// Passes the permission rent refunded to the deposit's owner on to whoever paid for the permission.
+fn forward_permission_rent<'info>(
+    owner: &Signer<'info>,
+    receiver: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    lamports: u64,
+) -> Result<()> {
+    if receiver.key() == owner.key() || lamports == 0 {
+        return Ok(());
+    }
+    anchor_lang::system_program::transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: owner.to_account_info(),
+                to: receiver.to_account_info(),
+            },
+        ),
+        lamports,
+    )
+}
+
+/// Grows a legacy account to `space` and rewrites it in the current layout as `migrated`.
+fn migrate_account<'info>(
+    payer: &Signer<'info>,
+    account: &UncheckedAccount<'info>,
+    system_program: &Program<'info, System>,
+    migrated: &impl AccountSerialize,
+    space: usize,
+) -> Result<()> {
+    grow_account(payer, account, system_program, space)?;
+    let mut data = account.try_borrow_mut_data()?;
+    migrated.try_serialize(&mut &mut data[..])
+}
+
+/// Rejects mints the shared per-mint vault cannot safely hold, see `supported_mints`.
+fn validate_mint(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    require!(
+        supported_mints::is_supported_mint(&mint.to_account_info())?,
+        ErrorCode::UnsupportedMint
+    );
+    Ok(())
 }
 
 fn validate_username(username: &str) -> Result<()> {
@@ -800,7 +1471,7 @@ fn validate_username(username: &str) -> Result<()> {
     require!(
         username
             .bytes()
-            .all(|b| (b'a'..=b'z').contains(&b) || (b'0'..=b'9').contains(&b) || b == b'_'),
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_'),
         ErrorCode::InvalidUsername
     );
     Ok(())
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountDeserialize, AccountSerialize, AnchorSerialize, Discriminator, Space};
+use telegram_private_transfer::{Deposit, ErrorCode, UsernameDeposit, Vault};
+
+/// What `migrate_account` leaves behind: `migrated` written over an account grown to `space`.
+fn grown_account(migrated: &impl AccountSerialize, space: usize) -> Vec<u8> {
+    let mut data = vec![0; space];
+    migrated.try_serialize(&mut data.as_mut_slice()).unwrap();
+    data
+}
+
+#[test]
+fn legacy_vaults_only_load_once_migrated_with_the_attested_total() {
+    // discriminator and `_dummy`, from before per-mint accounting
+    let legacy = [Vault::DISCRIMINATOR, &[0]].concat();
+    // what `modify_balance` and the claims run into before `migrate_vault`
+    assert!(Vault::try_deserialize(&mut legacy.as_slice()).is_err());
+
+    let migrated = Vault::try_from_legacy(&legacy, 1_000).unwrap();
+    let data = grown_account(&migrated, 8 + Vault::INIT_SPACE);
+    let vault = Vault::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(vault.total_shielded, 1_000);
+
+    let err = Vault::try_from_legacy(&data, 0).err().unwrap();
+    assert_eq!(err, ErrorCode::VaultAlreadyMigrated.into());
+}
+
+#[test]
+fn legacy_deposits_grow_without_recorded_payers() {
+    let user = Pubkey::new_from_array([7; 32]);
+    let token_mint = Pubkey::new_from_array([9; 32]);
+    let mut legacy = Deposit::DISCRIMINATOR.to_vec();
+    legacy.extend((user, token_mint, 1_000u64).try_to_vec().unwrap());
+
+    let migrated = Deposit::try_from_legacy(&legacy).unwrap();
+    let data = grown_account(&migrated, 8 + Deposit::INIT_SPACE);
+    let deposit = Deposit::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(deposit.user, user);
+    assert_eq!(deposit.token_mint, token_mint);
+    assert_eq!(deposit.amount, 1_000);
+    assert_eq!(deposit.payer, None);
+    assert_eq!(deposit.permission_payer, None);
+
+    let err = Deposit::try_from_legacy(&data).err().unwrap();
+    assert_eq!(err, ErrorCode::DepositAlreadyMigrated.into());
+}
+
+#[test]
+fn legacy_username_deposits_grow_without_recorded_payers() {
+    let token_mint = Pubkey::new_from_array([9; 32]);
+    let mut legacy = UsernameDeposit::DISCRIMINATOR.to_vec();
+    legacy.extend(
+        ("dig133713337".to_string(), token_mint, 1_000u64)
+            .try_to_vec()
+            .unwrap(),
+    );
+    // allocated for a 32-byte username
+    legacy.resize(8 + (4 + 32) + 32 + 8, 0);
+
+    let migrated = UsernameDeposit::try_from_legacy(&legacy).unwrap();
+    let data = grown_account(&migrated, 8 + UsernameDeposit::INIT_SPACE);
+    let deposit = UsernameDeposit::try_deserialize(&mut data.as_slice()).unwrap();
+    assert_eq!(deposit.username, "dig133713337");
+    assert_eq!(deposit.token_mint, token_mint);
+    assert_eq!(deposit.amount, 1_000);
+    assert_eq!(deposit.payer, None);
+    assert_eq!(deposit.permission_payer, None);
+
+    let err = UsernameDeposit::try_from_legacy(&data).err().unwrap();
+    assert_eq!(err, ErrorCode::DepositAlreadyMigrated.into());
+}
@@ -0,0 +1,580 @@
+//! Host-side client for `telegram_transfer`: PDA helpers, instruction builders
+//! and decoders for its accounts and events.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use anchor_spl::associated_token::{
+    get_associated_token_address, get_associated_token_address_with_program_id,
+};
+use anchor_spl::token::spl_token::native_mint;
+use telegram_verification::event_log::{decode_event, program_data};
+use telegram_verification::{TelegramSession, RELAYER_USAGE_SEED, SESSION_SEED};
+
+use crate::{
+    accounts, instruction, Deposit, DepositClaimed, DepositCreated, DepositRefunded, TokenDeposit,
+    TokenDepositClaimed, TokenDepositCreated, TokenDepositRefunded, DEPOSIT_ID_SEED,
+    DEPOSIT_ITEM_SEED, DEPOSIT_SEED, ID, PRIVATE_DEPOSIT_SEED, PRIVATE_VAULT_SEED,
+    TOKEN_DEPOSIT_SEED, TOKEN_VAULT_SEED,
+};
+
+// ---------------- PDAs ----------------
+
+/// Running-balance deposit from `depositor` to `username`.
+pub fn find_deposit_pda(depositor: &Pubkey, username: &str) -> Pubkey {
+    Pubkey::find_program_address(
+        &[DEPOSIT_SEED, depositor.as_ref(), username.as_bytes()],
+        &ID,
+    )
+    .0
+}
+
+/// Running-balance deposit from `depositor` pinned to a Telegram user id.
+pub fn find_telegram_id_deposit_pda(depositor: &Pubkey, telegram_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            DEPOSIT_ID_SEED,
+            depositor.as_ref(),
+            telegram_id.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Itemized deposit number `nonce` from `depositor` to `username`.
+pub fn find_deposit_item_pda(depositor: &Pubkey, username: &str, nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            DEPOSIT_ITEM_SEED,
+            depositor.as_ref(),
+            username.as_bytes(),
+            nonce.to_le_bytes().as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Every itemized deposit from `depositor` to `username`, given the `last_nonce`
+/// of their running deposit.
+pub fn find_deposit_item_pdas(depositor: &Pubkey, username: &str, last_nonce: u64) -> Vec<Pubkey> {
+    (0..last_nonce)
+        .map(|nonce| find_deposit_item_pda(depositor, username, nonce))
+        .collect()
+}
+
+/// Address of an already decoded deposit.
+pub fn deposit_address(deposit: &Deposit) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            deposit.seed_prefix(),
+            deposit.user.as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+pub fn find_token_vault_pda(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[TOKEN_VAULT_SEED, mint.as_ref()], &ID).0
+}
+
+pub fn find_token_deposit_pda(depositor: &Pubkey, mint: &Pubkey, username: &str) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            TOKEN_DEPOSIT_SEED,
+            depositor.as_ref(),
+            mint.as_ref(),
+            username.as_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// `TelegramSession` of `user_wallet`, which authorizes its claims.
+pub fn find_session_pda(user_wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[SESSION_SEED, user_wallet.as_ref()],
+        &telegram_verification::ID,
+    )
+    .0
+}
+
+/// What `user_wallet` owes `relayer`, settled out of its claims.
+pub fn find_relayer_usage_pda(relayer: &Pubkey, user_wallet: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[RELAYER_USAGE_SEED, relayer.as_ref(), user_wallet.as_ref()],
+        &telegram_verification::ID,
+    )
+    .0
+}
+
+/// wSOL `Deposit` of `owner` in `telegram_private_transfer`.
+pub fn find_private_deposit_pda(owner: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            PRIVATE_DEPOSIT_SEED,
+            owner.as_ref(),
+            native_mint::ID.as_ref(),
+        ],
+        &telegram_private_transfer::ID,
+    )
+    .0
+}
+
+/// wSOL vault of `telegram_private_transfer`.
+pub fn find_private_vault_pda() -> Pubkey {
+    Pubkey::find_program_address(
+        &[PRIVATE_VAULT_SEED, native_mint::ID.as_ref()],
+        &telegram_private_transfer::ID,
+    )
+    .0
+}
+
+// ---------------- Instructions ----------------
+
+/// `relayer_usage`, `relayer` and `verification_program` of a claim by `user_wallet`,
+/// whose session names `relayer` (`TelegramSession::relayer`).
+fn relayer_accounts(
+    relayer: Option<Pubkey>,
+    user_wallet: &Pubkey,
+) -> (Option<Pubkey>, Option<Pubkey>, Option<Pubkey>) {
+    match relayer {
+        Some(relayer) => (
+            Some(find_relayer_usage_pda(&relayer, user_wallet)),
+            Some(relayer),
+            Some(telegram_verification::ID),
+        ),
+        None => (None, None, None),
+    }
+}
+
+fn build_ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn deposit_for_username_ix(
+    payer: Pubkey,
+    depositor: Pubkey,
+    username: &str,
+    amount: u64,
+) -> Instruction {
+    build_ix(
+        accounts::DepositForUsername {
+            payer,
+            depositor,
+            deposit: find_deposit_pda(&depositor, username),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::DepositForUsername {
+            username: username.to_string(),
+            amount,
+        },
+    )
+}
+
+pub fn deposit_for_telegram_id_ix(
+    payer: Pubkey,
+    depositor: Pubkey,
+    telegram_id: u64,
+    amount: u64,
+) -> Instruction {
+    build_ix(
+        accounts::DepositForTelegramId {
+            payer,
+            depositor,
+            deposit: find_telegram_id_deposit_pda(&depositor, telegram_id),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::DepositForTelegramId {
+            telegram_id,
+            amount,
+        },
+    )
+}
+
+/// `nonce` must be the `last_nonce` of the running deposit from `depositor` to `username`.
+pub fn deposit_item_for_username_ix(
+    payer: Pubkey,
+    depositor: Pubkey,
+    username: &str,
+    nonce: u64,
+    amount: u64,
+    memo: &str,
+    sender_name: &str,
+) -> Instruction {
+    build_ix(
+        accounts::DepositItemForUsername {
+            payer,
+            depositor,
+            running_deposit: find_deposit_pda(&depositor, username),
+            deposit: find_deposit_item_pda(&depositor, username, nonce),
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::DepositItemForUsername {
+            username: username.to_string(),
+            nonce,
+            amount,
+            memo: memo.to_string(),
+            sender_name: sender_name.to_string(),
+        },
+    )
+}
+
+pub fn refund_deposit_ix(depositor: Pubkey, deposit: Pubkey, amount: u64) -> Instruction {
+    build_ix(
+        accounts::RefundDeposit { depositor, deposit },
+        instruction::RefundDeposit { amount },
+    )
+}
+
+pub fn set_deposit_expiry_ix(
+    depositor: Pubkey,
+    deposit: Pubkey,
+    expires_at: Option<i64>,
+) -> Instruction {
+    build_ix(
+        accounts::SetDepositExpiry { depositor, deposit },
+        instruction::SetDepositExpiry { expires_at },
+    )
+}
+
+pub fn set_deposit_schedule_ix(
+    depositor: Pubkey,
+    deposit: Pubkey,
+    unlock_at: i64,
+    vesting_end: Option<i64>,
+) -> Instruction {
+    build_ix(
+        accounts::SetDepositSchedule { depositor, deposit },
+        instruction::SetDepositSchedule {
+            unlock_at,
+            vesting_end,
+        },
+    )
+}
+
+pub fn cancel_vesting_ix(depositor: Pubkey, deposit: Pubkey) -> Instruction {
+    build_ix(
+        accounts::CancelVesting { depositor, deposit },
+        instruction::CancelVesting {},
+    )
+}
+
+pub fn refund_expired_deposit_ix(
+    cranker: Pubkey,
+    depositor: Pubkey,
+    deposit: Pubkey,
+) -> Instruction {
+    build_ix(
+        accounts::RefundExpiredDeposit {
+            cranker,
+            depositor,
+            deposit,
+        },
+        instruction::RefundExpiredDeposit {},
+    )
+}
+
+/// Grows a legacy `deposit` and escrows its balance; `payer` covers the extra rent.
+pub fn migrate_deposit_ix(payer: Pubkey, deposit: Pubkey) -> Instruction {
+    build_ix(
+        accounts::MigrateDeposit {
+            payer,
+            vault: Pubkey::find_program_address(&[crate::VAULT_SEED], &ID).0,
+            deposit,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::MigrateDeposit {},
+    )
+}
+
+/// Claims `amount` from `deposit` into `recipient`, authorized by its session.
+///
+/// `relayer` is the session's `relayer`; fees still owed to it come out of the claim.
+pub fn claim_deposit_ix(
+    recipient: Pubkey,
+    deposit: Pubkey,
+    amount: u64,
+    relayer: Option<Pubkey>,
+) -> Instruction {
+    let (relayer_usage, relayer, verification_program) = relayer_accounts(relayer, &recipient);
+    build_ix(
+        accounts::ClaimDeposit {
+            recipient,
+            deposit,
+            session: find_session_pda(&recipient),
+            relayer_usage,
+            relayer,
+            verification_program,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::ClaimDeposit { amount },
+    )
+}
+
+/// Claims `amount` from `deposit` into any `destination`, signed by the verified `user_wallet`.
+pub fn claim_deposit_to_ix(
+    user_wallet: Pubkey,
+    destination: Pubkey,
+    deposit: Pubkey,
+    amount: u64,
+    relayer: Option<Pubkey>,
+) -> Instruction {
+    let (relayer_usage, relayer, verification_program) = relayer_accounts(relayer, &user_wallet);
+    build_ix(
+        accounts::ClaimDepositTo {
+            user_wallet,
+            destination,
+            deposit,
+            session: find_session_pda(&user_wallet),
+            relayer_usage,
+            relayer,
+            verification_program,
+        },
+        instruction::ClaimDepositTo { amount },
+    )
+}
+
+/// Claims `amount` from `deposit` as wSOL into the private-transfer deposit of
+/// `destination`, which must already exist and must co-sign.
+pub fn claim_deposit_to_private_ix(
+    user_wallet: Pubkey,
+    destination: Pubkey,
+    deposit: Pubkey,
+    amount: u64,
+    relayer: Option<Pubkey>,
+) -> Instruction {
+    let (relayer_usage, relayer, verification_program) = relayer_accounts(relayer, &user_wallet);
+    let private_vault = find_private_vault_pda();
+    build_ix(
+        accounts::ClaimDepositToPrivate {
+            user_wallet,
+            destination,
+            deposit,
+            session: find_session_pda(&user_wallet),
+            destination_token_account: get_associated_token_address(&destination, &native_mint::ID),
+            private_deposit: find_private_deposit_pda(&destination),
+            private_vault,
+            private_vault_token_account: get_associated_token_address(
+                &private_vault,
+                &native_mint::ID,
+            ),
+            native_mint: native_mint::ID,
+            token_program: anchor_spl::token::ID,
+            associated_token_program: anchor_spl::associated_token::ID,
+            private_transfer_program: telegram_private_transfer::ID,
+            relayer_usage,
+            relayer,
+            verification_program,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::ClaimDepositToPrivate { amount },
+    )
+}
+
+/// Claims from several deposits at once; `amounts: None` claims every balance.
+pub fn claim_many_ix(
+    recipient: Pubkey,
+    deposits: &[Pubkey],
+    amounts: Option<Vec<u64>>,
+    relayer: Option<Pubkey>,
+) -> Instruction {
+    let (relayer_usage, relayer, verification_program) = relayer_accounts(relayer, &recipient);
+    let mut ix = build_ix(
+        accounts::ClaimMany {
+            recipient,
+            session: find_session_pda(&recipient),
+            relayer_usage,
+            relayer,
+            verification_program,
+        },
+        instruction::ClaimMany { amounts },
+    );
+    ix.accounts.extend(
+        deposits
+            .iter()
+            .map(|deposit| AccountMeta::new(*deposit, false)),
+    );
+    ix
+}
+
+/// Deposits `amount` of `mint` for `username` from the depositor's associated token account.
+pub fn deposit_token_for_username_ix(
+    payer: Pubkey,
+    depositor: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    username: &str,
+    amount: u64,
+) -> Instruction {
+    build_ix(
+        accounts::DepositTokenForUsername {
+            payer,
+            depositor,
+            mint,
+            depositor_token_account: get_associated_token_address_with_program_id(
+                &depositor,
+                &mint,
+                &token_program,
+            ),
+            vault_token_account: find_token_vault_pda(&mint),
+            deposit: find_token_deposit_pda(&depositor, &mint, username),
+            token_program,
+            system_program: anchor_lang::system_program::ID,
+        },
+        instruction::DepositTokenForUsername {
+            username: username.to_string(),
+            amount,
+        },
+    )
+}
+
+/// Refunds `amount` of `deposit` into the depositor's associated token account.
+pub fn refund_token_deposit_ix(
+    depositor: Pubkey,
+    deposit: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    amount: u64,
+) -> Instruction {
+    build_ix(
+        accounts::RefundTokenDeposit {
+            depositor,
+            mint,
+            depositor_token_account: get_associated_token_address_with_program_id(
+                &depositor,
+                &mint,
+                &token_program,
+            ),
+            vault_token_account: find_token_vault_pda(&mint),
+            deposit,
+            token_program,
+            memo_program: anchor_spl::memo::ID,
+        },
+        instruction::RefundTokenDeposit { amount },
+    )
+}
+
+pub fn set_token_deposit_expiry_ix(
+    depositor: Pubkey,
+    deposit: Pubkey,
+    expires_at: Option<i64>,
+) -> Instruction {
+    build_ix(
+        accounts::SetTokenDepositExpiry { depositor, deposit },
+        instruction::SetTokenDepositExpiry { expires_at },
+    )
+}
+
+/// Returns an expired token deposit to the depositor's associated token account.
+pub fn refund_expired_token_deposit_ix(
+    depositor: Pubkey,
+    deposit: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+) -> Instruction {
+    build_ix(
+        accounts::RefundExpiredTokenDeposit {
+            mint,
+            depositor_token_account: get_associated_token_address_with_program_id(
+                &depositor,
+                &mint,
+                &token_program,
+            ),
+            vault_token_account: find_token_vault_pda(&mint),
+            deposit,
+            token_program,
+            memo_program: anchor_spl::memo::ID,
+        },
+        instruction::RefundExpiredTokenDeposit {},
+    )
+}
+
+/// Claims `amount` of `deposit` into the associated token account of `user_wallet`,
+/// authorized by its session.
+pub fn claim_token_deposit_ix(
+    user_wallet: Pubkey,
+    deposit: Pubkey,
+    mint: Pubkey,
+    token_program: Pubkey,
+    amount: u64,
+) -> Instruction {
+    build_ix(
+        accounts::ClaimTokenDeposit {
+            mint,
+            recipient_token_account: get_associated_token_address_with_program_id(
+                &user_wallet,
+                &mint,
+                &token_program,
+            ),
+            vault_token_account: find_token_vault_pda(&mint),
+            deposit,
+            session: find_session_pda(&user_wallet),
+            token_program,
+            memo_program: anchor_spl::memo::ID,
+        },
+        instruction::ClaimTokenDeposit { amount },
+    )
+}
+
+// ---------------- Decoders ----------------
+
+pub fn decode_deposit(data: &[u8]) -> Result<Deposit> {
+    Deposit::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_token_deposit(data: &[u8]) -> Result<TokenDeposit> {
+    TokenDeposit::try_deserialize(&mut &data[..])
+}
+
+pub fn decode_session(data: &[u8]) -> Result<TelegramSession> {
+    TelegramSession::try_deserialize(&mut &data[..])
+}
+
+/// Any deposit event, decoded from program logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransferEvent {
+    Created(DepositCreated),
+    Refunded(DepositRefunded),
+    Claimed(DepositClaimed),
+    TokenCreated(TokenDepositCreated),
+    TokenRefunded(TokenDepositRefunded),
+    TokenClaimed(TokenDepositClaimed),
+}
+
+impl TransferEvent {
+    /// Decodes the SOL or token deposit event logged on a `Program data: ` line.
+    ///
+    /// Returns `None` for lines that are not deposit events, e.g. the session
+    /// events `telegram_verification` logs during a claim.
+    pub fn from_log(line: &str) -> Option<Self> {
+        Self::decode(&program_data(line)?)
+    }
+
+    /// Decodes the bytes `emit!` wrote for a deposit event.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        decode_event(data)
+            .map(Self::Created)
+            .or_else(|| decode_event(data).map(Self::Refunded))
+            .or_else(|| decode_event(data).map(Self::Claimed))
+            .or_else(|| decode_event(data).map(Self::TokenCreated))
+            .or_else(|| decode_event(data).map(Self::TokenRefunded))
+            .or_else(|| decode_event(data).map(Self::TokenClaimed))
+    }
+
+    /// Collects the deposit events from a transaction's log messages.
+    ///
+    /// Event names are not namespaced, so pass logs of this program's invocations only.
+    pub fn from_logs<'a>(logs: impl IntoIterator<Item = &'a str>) -> Vec<Self> {
+        logs.into_iter().filter_map(Self::from_log).collect()
+    }
+}
@@ -1,13 +1,48 @@
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::memo::{build_memo, BuildMemo, Memo};
+use anchor_spl::token::{spl_token::native_mint, sync_native, SyncNative, Token};
+use anchor_spl::token_2022::spl_token_2022::extension::{
+    memo_transfer::memo_required, StateWithExtensions,
+};
+use anchor_spl::token_2022::spl_token_2022::state::Account as SplTokenAccount;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{
+    transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
-use telegram_verification::TelegramSession;
+use telegram_private_transfer::cpi::accounts::ModifyDeposit as PrivateModifyDeposit;
+use telegram_private_transfer::program::TelegramPrivateTransfer;
+use telegram_private_transfer::{
+    ModifyDepositArgs, DEPOSIT_PDA_SEED as PRIVATE_DEPOSIT_SEED,
+    VAULT_PDA_SEED as PRIVATE_VAULT_SEED,
+};
+use telegram_verification::cpi::accounts::SettleRelayerFee;
+use telegram_verification::program::TelegramVerification;
+use telegram_verification::{RelayerUsage, TelegramSession};
+
+#[cfg(not(target_os = "solana"))]
+pub mod client;
 
 declare_id!("4ewpzEPF5xrVAHeRkoe7XS1yKFGQBekD7PgFwEz9SaxY");
 
 // Seed constants
 const DEPOSIT_SEED: &[u8] = b"deposit";
+const DEPOSIT_ID_SEED: &[u8] = b"deposit_id";
+const DEPOSIT_ITEM_SEED: &[u8] = b"deposit_item";
 const VAULT_SEED: &[u8] = b"vault";
+const TOKEN_DEPOSIT_SEED: &[u8] = b"token_deposit";
+const TOKEN_VAULT_SEED: &[u8] = b"token_vault";
+
+pub const MAX_MEMO_LEN: usize = 128;
+pub const MAX_SENDER_NAME_LEN: usize = 64;
+
+/// Paid out of an expired deposit to whoever cranks its refund.
+const CRANK_REWARD_LAMPORTS: u64 = 10_000;
+
+/// Memo attached to vault payouts into token accounts that require incoming memos.
+const WITHDRAW_MEMO: &[u8] = b"telegram-transfer withdrawal";
 
 #[program]
 pub mod telegram_transfer {
@@ -20,122 +55,653 @@ pub mod telegram_transfer {
         amount: u64,
     ) -> Result<()> {
         let deposit = &mut ctx.accounts.deposit;
-        let vault = &mut ctx.accounts.vault;
+        init_running_deposit(
+            deposit,
+            ctx.accounts.depositor.key(),
+            &username,
+            ctx.bumps.deposit,
+        )?;
+
+        fund_deposit(
+            deposit,
+            &ctx.accounts.depositor,
+            &ctx.accounts.system_program,
+            amount,
+        )
+    }
+
+    // 1b) Depositor earmarks SOL for a Telegram user id, which survives username changes.
+    pub fn deposit_for_telegram_id(
+        ctx: Context<DepositForTelegramId>,
+        telegram_id: u64,
+        amount: u64,
+    ) -> Result<()> {
+        require!(telegram_id > 0, ErrorCode::InvalidTelegramId);
+
+        let deposit = &mut ctx.accounts.deposit;
 
         // init deposit on first use
         if deposit.user == Pubkey::default() {
             deposit.user = ctx.accounts.depositor.key();
-            deposit.username = username.clone();
+            deposit.username = String::new();
             deposit.amount = 0;
             deposit.last_nonce = 0;
+            deposit.telegram_id = Some(telegram_id);
+            deposit.expires_at = None;
+            deposit.escrowed = true;
+            deposit.nonce = None;
+            deposit.created_at = Clock::get()?.unix_timestamp;
+            deposit.memo = String::new();
+            deposit.sender_name = String::new();
+            deposit.unlock_at = None;
+            deposit.vesting_end = None;
+            deposit.claimed = 0;
+            deposit.vested_locked = 0;
+            deposit.bump = ctx.bumps.deposit;
         }
 
-        // init vault bump (once)
-        if vault.bump == 0 {
-            vault.bump = ctx.bumps.vault;
-        }
+        fund_deposit(
+            deposit,
+            &ctx.accounts.depositor,
+            &ctx.accounts.system_program,
+            amount,
+        )
+    }
 
+    // 1c) Depositor sends a standalone, itemized deposit to a username. Each `nonce`
+    // opens a separate claimable item instead of topping up a running balance.
+    // Nonces count up from 0 in the running deposit's `last_nonce`, so a recipient
+    // can list every item a depositor sent them.
+    pub fn deposit_item_for_username(
+        ctx: Context<DepositItemForUsername>,
+        username: String,
+        nonce: u64,
+        amount: u64,
+        memo: String,
+        sender_name: String,
+    ) -> Result<()> {
+        require!(memo.len() <= MAX_MEMO_LEN, ErrorCode::InvalidMemo);
         require!(
-            ctx.accounts.depositor.key() == deposit.user,
-            ErrorCode::InvalidDepositor
+            sender_name.len() <= MAX_SENDER_NAME_LEN,
+            ErrorCode::InvalidSenderName
+        );
+
+        let running_deposit = &mut ctx.accounts.running_deposit;
+        init_running_deposit(
+            running_deposit,
+            ctx.accounts.depositor.key(),
+            &username,
+            ctx.bumps.running_deposit,
+        )?;
+        require!(nonce == running_deposit.last_nonce, ErrorCode::InvalidNonce);
+        running_deposit.last_nonce = nonce.checked_add(1).ok_or(ErrorCode::Overflow)?;
+
+        let deposit = &mut ctx.accounts.deposit;
+        deposit.user = ctx.accounts.depositor.key();
+        deposit.username = username;
+        deposit.amount = 0;
+        deposit.last_nonce = 0;
+        deposit.telegram_id = None;
+        deposit.expires_at = None;
+        deposit.escrowed = true;
+        deposit.nonce = Some(nonce);
+        deposit.created_at = Clock::get()?.unix_timestamp;
+        deposit.memo = memo;
+        deposit.sender_name = sender_name;
+        deposit.unlock_at = None;
+        deposit.vesting_end = None;
+        deposit.claimed = 0;
+        deposit.vested_locked = 0;
+        deposit.bump = ctx.bumps.deposit;
+
+        fund_deposit(
+            deposit,
+            &ctx.accounts.depositor,
+            &ctx.accounts.system_program,
+            amount,
+        )
+    }
+
+    // 2a) Refund: depositor pulls back to own wallet.
+    pub fn refund_deposit(ctx: Context<RefundDeposit>, amount: u64) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+
+        // ensure this deposit belongs to signer
+        require_keys_eq!(deposit.user, ctx.accounts.depositor.key());
+        // a vesting deposit can only give back its unvested part, via cancel_vesting
+        require!(!deposit.is_vesting(), ErrorCode::DepositVesting);
+        // what vested before a cancelled schedule belongs to the recipient
+        require!(amount <= deposit.refundable(), ErrorCode::DepositLocked);
+
+        // move lamports: deposit -> depositor
+        pay_from_deposit(deposit, &ctx.accounts.depositor.to_account_info(), amount)?;
+        emit!(DepositRefunded {
+            deposit: deposit.key(),
+            depositor: deposit.user,
+            amount,
+            remaining: deposit.amount,
+            crank_reward: 0,
+        });
+        Ok(())
+    }
+
+    // 2b) Claim: recipient gets SOL if the username is verified
+    pub fn claim_deposit(ctx: Context<ClaimDeposit>, amount: u64) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        let session = &ctx.accounts.session;
+
+        // --- verification ---
+        let now = Clock::get()?.unix_timestamp;
+        require!(session.is_verified(now as u64), ErrorCode::NotVerified);
+        require!(
+            session.user_wallet == ctx.accounts.recipient.key(),
+            ErrorCode::InvalidRecipient
         );
+        require_claimable(deposit, session, now)?;
+
+        // --- relayer fees ---
+        let amount = settle_relayer_fee(
+            deposit,
+            session,
+            ctx.accounts.relayer_usage.as_mut(),
+            ctx.accounts.relayer.as_ref(),
+            ctx.accounts.verification_program.as_ref(),
+            amount,
+            now,
+        )?;
+
+        // --- transfer ---
+        let recipient = ctx.accounts.recipient.to_account_info();
+        claim_from_deposit(deposit, &recipient, amount, now)?;
+        emit_claimed(deposit, &recipient, amount);
+        Ok(())
+    }
+
+    // 2g) Claim to any destination: the verified wallet signs and names where the
+    // funds go, so a throwaway Mini App wallet can pay out to e.g. a hardware wallet.
+    pub fn claim_deposit_to(ctx: Context<ClaimDepositTo>, amount: u64) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        let now = Clock::get()?.unix_timestamp;
+        require_claimable(deposit, &ctx.accounts.session, now)?;
+        let amount = settle_relayer_fee(
+            deposit,
+            &ctx.accounts.session,
+            ctx.accounts.relayer_usage.as_mut(),
+            ctx.accounts.relayer.as_ref(),
+            ctx.accounts.verification_program.as_ref(),
+            amount,
+            now,
+        )?;
+
+        let destination = ctx.accounts.destination.to_account_info();
+        claim_from_deposit(deposit, &destination, amount, now)?;
+        emit_claimed(deposit, &destination, amount);
+        Ok(())
+    }
+
+    // 2h) Claim into a private-transfer deposit: the lamports are wrapped into the
+    // destination's wSOL account and credited to its existing private `Deposit`.
+    pub fn claim_deposit_to_private(
+        ctx: Context<ClaimDepositToPrivate>,
+        amount: u64,
+    ) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        let now = Clock::get()?.unix_timestamp;
+        require_claimable(deposit, &ctx.accounts.session, now)?;
+        let amount = settle_relayer_fee(
+            deposit,
+            &ctx.accounts.session,
+            ctx.accounts.relayer_usage.as_mut(),
+            ctx.accounts.relayer.as_ref(),
+            ctx.accounts.verification_program.as_ref(),
+            amount,
+            now,
+        )?;
+        require!(amount > 0, ErrorCode::InsufficientDeposit);
+
+        // --- wrap: deposit -> destination wSOL account ---
+        let wsol = ctx.accounts.destination_token_account.to_account_info();
+        claim_from_deposit(deposit, &wsol, amount, now)?;
+        sync_native(CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            SyncNative {
+                account: wsol.clone(),
+            },
+        ))?;
 
-        // depositor -> vault lamports
-        transfer(
+        // --- credit: destination wSOL account -> private-transfer vault ---
+        telegram_private_transfer::cpi::modify_balance(
             CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.depositor.to_account_info(),
-                    to: vault.to_account_info(),
+                ctx.accounts.private_transfer_program.to_account_info(),
+                PrivateModifyDeposit {
+                    payer: ctx.accounts.user_wallet.to_account_info(),
+                    user: ctx.accounts.destination.to_account_info(),
+                    vault: ctx.accounts.private_vault.to_account_info(),
+                    deposit: ctx.accounts.private_deposit.to_account_info(),
+                    user_token_account: wsol,
+                    vault_token_account: ctx.accounts.private_vault_token_account.to_account_info(),
+                    token_mint: ctx.accounts.native_mint.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                    associated_token_program: ctx
+                        .accounts
+                        .associated_token_program
+                        .to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
                 },
             ),
-            amount,
+            ModifyDepositArgs {
+                amount,
+                increase: true,
+            },
         )?;
 
-        // book-keeping
-        deposit.amount = deposit
-            .amount
-            .checked_add(amount)
-            .ok_or(ErrorCode::Overflow)?;
-        vault.total_deposited = vault
-            .total_deposited
-            .checked_add(amount)
-            .ok_or(ErrorCode::Overflow)?;
+        emit_claimed(deposit, &ctx.accounts.destination, amount);
         Ok(())
     }
 
-    // 2a) Refund: depositor pulls back to own wallet.
-    pub fn refund_deposit(ctx: Context<RefundDeposit>, amount: u64) -> Result<()> {
+    // 2f) Batch claim: deposits come in as writable remaining accounts. `amounts`
+    // claims that much from each deposit in order; `None` claims everything releasable.
+    pub fn claim_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>,
+        amounts: Option<Vec<u64>>,
+    ) -> Result<()> {
+        let session = &ctx.accounts.session;
+        let recipient = ctx.accounts.recipient.to_account_info();
+        let deposits = ctx.remaining_accounts;
+
+        require!(!deposits.is_empty(), ErrorCode::InvalidBatch);
+        if let Some(amounts) = &amounts {
+            require!(amounts.len() == deposits.len(), ErrorCode::InvalidBatch);
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        for (i, deposit_ai) in deposits.iter().enumerate() {
+            let mut deposit = Account::<Deposit>::try_from(deposit_ai)?;
+            let expected = Pubkey::create_program_address(
+                &[
+                    deposit.seed_prefix(),
+                    deposit.user.as_ref(),
+                    deposit.recipient_seed().as_ref(),
+                    deposit.nonce_seed().as_ref(),
+                    &[deposit.bump],
+                ],
+                &crate::ID,
+            )
+            .map_err(|_| ErrorCode::InvalidDeposit)?;
+            require_keys_eq!(deposit_ai.key(), expected, ErrorCode::InvalidDeposit);
+            require_claimable(&deposit, session, now)?;
+
+            let amount = match &amounts {
+                Some(amounts) => amounts[i],
+                None => deposit.releasable(now),
+            };
+            if amount == 0 {
+                continue;
+            }
+            let amount = settle_relayer_fee(
+                &mut deposit,
+                session,
+                ctx.accounts.relayer_usage.as_mut(),
+                ctx.accounts.relayer.as_ref(),
+                ctx.accounts.verification_program.as_ref(),
+                amount,
+                now,
+            )?;
+
+            claim_from_deposit(&mut deposit, &recipient, amount, now)?;
+            // persist now so a deposit listed twice sees its reduced balance
+            deposit.exit(&crate::ID)?;
+            emit_claimed(&deposit, &recipient, amount);
+        }
+        Ok(())
+    }
+
+    // 2c) Depositor sets or clears the time after which the deposit can only be refunded.
+    pub fn set_deposit_expiry(
+        ctx: Context<SetDepositExpiry>,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        let now = Clock::get()?.unix_timestamp;
+
+        // an expired deposit stays expired so its outcome can't flip
+        require!(!deposit.is_expired(now), ErrorCode::DepositExpired);
+        // expiry would let the depositor take back vested funds
+        require!(!deposit.is_vesting(), ErrorCode::DepositVesting);
+        require!(deposit.vested_locked == 0, ErrorCode::DepositLocked);
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > now, ErrorCode::InvalidExpiry);
+        }
+
+        deposit.expires_at = expires_at;
+        Ok(())
+    }
+
+    // 2d) Crank: anyone returns an expired deposit to its depositor for a small reward.
+    pub fn refund_expired_deposit(ctx: Context<RefundExpiredDeposit>) -> Result<()> {
         let deposit = &mut ctx.accounts.deposit;
+
+        require!(
+            deposit.is_expired(Clock::get()?.unix_timestamp),
+            ErrorCode::DepositNotExpired
+        );
+        let amount = deposit.refundable();
+        require!(amount > 0, ErrorCode::InsufficientDeposit);
+
+        // move lamports: deposit -> cranker (reward) + depositor (rest)
+        let reward = amount.min(CRANK_REWARD_LAMPORTS);
+        pay_from_deposit(deposit, &ctx.accounts.cranker.to_account_info(), reward)?;
+        pay_from_deposit(
+            deposit,
+            &ctx.accounts.depositor.to_account_info(),
+            amount - reward,
+        )?;
+        emit!(DepositRefunded {
+            deposit: deposit.key(),
+            depositor: deposit.user,
+            amount: amount - reward,
+            remaining: deposit.amount,
+            crank_reward: reward,
+        });
+        Ok(())
+    }
+
+    // 2e) Migration: anyone grows a legacy deposit to the current layout and moves
+    // its balance out of the shared vault into the deposit account itself.
+    pub fn migrate_deposit(ctx: Context<MigrateDeposit>) -> Result<()> {
+        let deposit_ai = &ctx.accounts.deposit;
         let vault = &mut ctx.accounts.vault;
         let vault_ai = vault.to_account_info();
 
-        // ensure this deposit belongs to signer
-        require_keys_eq!(deposit.user, ctx.accounts.depositor.key());
+        let space = 8 + Deposit::INIT_SPACE;
+        let mut deposit = Deposit::try_from_legacy(&deposit_ai.try_borrow_data()?)?;
+        let (expected, bump) = Pubkey::find_program_address(
+            &[
+                deposit.seed_prefix(),
+                deposit.user.as_ref(),
+                deposit.recipient_seed().as_ref(),
+                deposit.nonce_seed().as_ref(),
+            ],
+            &crate::ID,
+        );
+        require_keys_eq!(deposit_ai.key(), expected, ErrorCode::InvalidDeposit);
+        let amount = deposit.amount;
+
+        // grow the account; the payer covers the extra rent
+        let shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(deposit_ai.lamports());
+        if shortfall > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: deposit_ai.to_account_info(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        deposit_ai.resize(space)?;
 
         // keep vault rent-exempt
         let rent_min = Rent::get()?.minimum_balance(8 + Vault::INIT_SPACE);
         let available = vault_ai.lamports().saturating_sub(rent_min);
         require!(available >= amount, ErrorCode::InsufficientVault);
 
-        // move lamports: vault -> depositor
+        // move lamports: vault -> deposit
         **vault_ai.try_borrow_mut_lamports()? -= amount;
-        **ctx
-            .accounts
-            .depositor
-            .to_account_info()
-            .try_borrow_mut_lamports()? += amount;
+        **deposit_ai.try_borrow_mut_lamports()? += amount;
 
         // accounting
-        deposit.amount = deposit
-            .amount
-            .checked_sub(amount)
-            .ok_or(ErrorCode::InsufficientDeposit)?;
         vault.total_deposited = vault
             .total_deposited
             .checked_sub(amount)
             .ok_or(ErrorCode::Overflow)?;
+        deposit.escrowed = true;
+        deposit.bump = bump;
+
+        let mut data = deposit_ai.try_borrow_mut_data()?;
+        data.fill(0);
+        deposit.try_serialize(&mut &mut data[..])?;
         Ok(())
     }
 
-    // 2b) Claim: recipient gets SOL if the username is verified
-    pub fn claim_deposit(ctx: Context<ClaimDeposit>, amount: u64) -> Result<()> {
+    // 2i) Depositor puts the deposit on a schedule: nothing is claimable before
+    // `unlock_at`, then the balance vests linearly until `vesting_end` (all at once
+    // when `None`). The schedule is fixed once set; it can only be cancelled.
+    pub fn set_deposit_schedule(
+        ctx: Context<SetDepositSchedule>,
+        unlock_at: i64,
+        vesting_end: Option<i64>,
+    ) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+
+        require!(!deposit.is_vesting(), ErrorCode::DepositVesting);
+        require!(deposit.expires_at.is_none(), ErrorCode::InvalidSchedule);
+        // a new schedule must not re-lock what a cancelled one already vested
+        require!(deposit.vested_locked == 0, ErrorCode::InvalidSchedule);
+        if let Some(vesting_end) = vesting_end {
+            require!(vesting_end > unlock_at, ErrorCode::InvalidSchedule);
+        }
+
+        deposit.unlock_at = Some(unlock_at);
+        deposit.vesting_end = vesting_end;
+        // vesting covers the current balance; earlier claims don't count towards it
+        deposit.claimed = 0;
+        Ok(())
+    }
+
+    // 2j) Depositor cancels the schedule and takes back the unvested remainder.
+    // What has vested stays in the deposit as `vested_locked`, claimable right away
+    // and out of reach of refunds.
+    pub fn cancel_vesting(ctx: Context<CancelVesting>) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+
+        require!(deposit.is_vesting(), ErrorCode::InvalidSchedule);
+        let unvested = deposit.cancel_schedule(Clock::get()?.unix_timestamp);
+
+        // move lamports: deposit -> depositor
+        pay_from_deposit(deposit, &ctx.accounts.depositor.to_account_info(), unvested)?;
+
+        emit!(DepositRefunded {
+            deposit: deposit.key(),
+            depositor: deposit.user,
+            amount: unvested,
+            remaining: deposit.amount,
+            crank_reward: 0,
+        });
+        Ok(())
+    }
+
+    // 3) Depositor earmarks SPL / Token-2022 tokens for a username. Transfer-fee
+    // mints are allowed; the deposit is credited with what arrives net of the fee.
+    pub fn deposit_token_for_username(
+        ctx: Context<DepositTokenForUsername>,
+        username: String,
+        amount: u64,
+    ) -> Result<()> {
+        validate_mint(&ctx.accounts.mint)?;
+
+        let deposit = &mut ctx.accounts.deposit;
+        let vault = &mut ctx.accounts.vault_token_account;
+
+        // init deposit on first use
+        if deposit.user == Pubkey::default() {
+            deposit.user = ctx.accounts.depositor.key();
+            deposit.username = username;
+            deposit.mint = ctx.accounts.mint.key();
+            deposit.amount = 0;
+            deposit.expires_at = None;
+        }
+        require!(
+            ctx.accounts.depositor.key() == deposit.user,
+            ErrorCode::InvalidDepositor
+        );
+        require!(
+            !deposit.is_expired(Clock::get()?.unix_timestamp),
+            ErrorCode::DepositExpired
+        );
+
+        // depositor -> vault tokens
+        let balance_before = vault.amount;
+        transfer_checked(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                TransferChecked {
+                    from: ctx.accounts.depositor_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    to: vault.to_account_info(),
+                    authority: ctx.accounts.depositor.to_account_info(),
+                },
+            ),
+            amount,
+            ctx.accounts.mint.decimals,
+        )?;
+
+        // book what actually arrived, net of any Token-2022 transfer fee
+        vault.reload()?;
+        let received = vault
+            .amount
+            .checked_sub(balance_before)
+            .ok_or(ErrorCode::Overflow)?;
+        deposit.amount = deposit
+            .amount
+            .checked_add(received)
+            .ok_or(ErrorCode::Overflow)?;
+
+        emit!(TokenDepositCreated {
+            deposit: deposit.key(),
+            depositor: deposit.user,
+            username: deposit.username.clone(),
+            mint: deposit.mint,
+            amount: received,
+            total: deposit.amount,
+        });
+        Ok(())
+    }
+
+    // 3a) Refund: depositor pulls tokens back to own token account.
+    pub fn refund_token_deposit(ctx: Context<RefundTokenDeposit>, amount: u64) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        require!(deposit.amount >= amount, ErrorCode::InsufficientDeposit);
+
+        withdraw_from_token_vault(
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.depositor_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            &ctx.accounts.memo_program,
+            ctx.bumps.vault_token_account,
+            amount,
+        )?;
+
+        deposit.amount -= amount;
+        emit!(TokenDepositRefunded {
+            deposit: deposit.key(),
+            depositor: deposit.user,
+            mint: deposit.mint,
+            amount,
+            remaining: deposit.amount,
+        });
+        Ok(())
+    }
+
+    // 3b) Claim: recipient gets tokens if the username is verified
+    pub fn claim_token_deposit(ctx: Context<ClaimTokenDeposit>, amount: u64) -> Result<()> {
         let deposit = &mut ctx.accounts.deposit;
         let session = &ctx.accounts.session;
-        let vault = &mut ctx.accounts.vault;
-        let vault_ai = vault.to_account_info();
-        let recipient_ai = ctx.accounts.recipient.to_account_info();
 
         // --- verification ---
-        require!(session.verified, ErrorCode::NotVerified);
+        require!(
+            session.is_verified(Clock::get()?.unix_timestamp as u64),
+            ErrorCode::NotVerified
+        );
         require!(
             session.username == deposit.username,
             ErrorCode::InvalidUsername
         );
         require!(
-            session.user_wallet == ctx.accounts.recipient.key(),
-            ErrorCode::InvalidRecipient
+            !deposit.is_expired(Clock::get()?.unix_timestamp),
+            ErrorCode::DepositExpired
         );
         require!(deposit.amount >= amount, ErrorCode::InsufficientDeposit);
-
-        let rent_min = Rent::get()?.minimum_balance(8 + Vault::INIT_SPACE);
-        let available = vault_ai.lamports().saturating_sub(rent_min);
-        require!(available >= amount, ErrorCode::InsufficientVault);
+        // tokens can't pay relayer fees; they stay owed to be settled by a SOL
+        // claim or `reimburse_relayer`, and the session can't be closed until then
 
         // --- transfer ---
-        **vault_ai.try_borrow_mut_lamports()? -= amount;
-        **recipient_ai.try_borrow_mut_lamports()? += amount;
+        withdraw_from_token_vault(
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.recipient_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            &ctx.accounts.memo_program,
+            ctx.bumps.vault_token_account,
+            amount,
+        )?;
 
         // --- accounting ---
-        deposit.amount = deposit
-            .amount
-            .checked_sub(amount)
-            .ok_or(ErrorCode::InsufficientDeposit)?;
-        vault.total_deposited = vault
-            .total_deposited
-            .checked_sub(amount)
-            .ok_or(ErrorCode::Overflow)?;
+        deposit.amount -= amount;
+        emit!(TokenDepositClaimed {
+            deposit: deposit.key(),
+            depositor: deposit.user,
+            recipient: ctx.accounts.recipient_token_account.key(),
+            mint: deposit.mint,
+            amount,
+            remaining: deposit.amount,
+        });
+        Ok(())
+    }
+
+    // 3c) Depositor sets or clears the time after which the token deposit can only be refunded.
+    pub fn set_token_deposit_expiry(
+        ctx: Context<SetTokenDepositExpiry>,
+        expires_at: Option<i64>,
+    ) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+        let now = Clock::get()?.unix_timestamp;
+
+        // an expired deposit stays expired so its outcome can't flip
+        require!(!deposit.is_expired(now), ErrorCode::DepositExpired);
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > now, ErrorCode::InvalidExpiry);
+        }
+
+        deposit.expires_at = expires_at;
+        Ok(())
+    }
+
+    // 3d) Crank: anyone returns an expired token deposit to its depositor. There
+    // is no reward, since the deposit holds no lamports to pay it from.
+    pub fn refund_expired_token_deposit(ctx: Context<RefundExpiredTokenDeposit>) -> Result<()> {
+        let deposit = &mut ctx.accounts.deposit;
+
+        require!(
+            deposit.is_expired(Clock::get()?.unix_timestamp),
+            ErrorCode::DepositNotExpired
+        );
+        let amount = deposit.amount;
+        require!(amount > 0, ErrorCode::InsufficientDeposit);
+
+        withdraw_from_token_vault(
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.mint,
+            &ctx.accounts.depositor_token_account.to_account_info(),
+            &ctx.accounts.token_program,
+            &ctx.accounts.memo_program,
+            ctx.bumps.vault_token_account,
+            amount,
+        )?;
 
+        deposit.amount = 0;
+        emit!(TokenDepositRefunded {
+            deposit: deposit.key(),
+            depositor: deposit.user,
+            mint: deposit.mint,
+            amount,
+            remaining: 0,
+        });
         Ok(())
     }
 }
@@ -152,17 +718,28 @@ pub struct DepositForUsername<'info> {
     #[account(
         init_if_needed,
         payer = payer,
-        space = 8 + Vault::INIT_SPACE,
-        seeds = [VAULT_SEED],
+        space = 8 + Deposit::INIT_SPACE,
+        seeds = [DEPOSIT_SEED, depositor.key().as_ref(), username.as_bytes()],
         bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub deposit: Account<'info, Deposit>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(telegram_id: u64)]
+pub struct DepositForTelegramId<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>, // rent for PDAs
+    #[account(mut)]
+    pub depositor: Signer<'info>, // funds come from here
 
     #[account(
         init_if_needed,
         payer = payer,
         space = 8 + Deposit::INIT_SPACE,
-        seeds = [DEPOSIT_SEED, depositor.key().as_ref(), username.as_bytes()],
+        seeds = [DEPOSIT_ID_SEED, depositor.key().as_ref(), telegram_id.to_le_bytes().as_ref()],
         bump
     )]
     pub deposit: Account<'info, Deposit>,
@@ -171,31 +748,136 @@ pub struct DepositForUsername<'info> {
 }
 
 #[derive(Accounts)]
-pub struct RefundDeposit<'info> {
+#[instruction(username: String, nonce: u64)]
+pub struct DepositItemForUsername<'info> {
     #[account(mut)]
-    pub depositor: Signer<'info>,
+    pub payer: Signer<'info>, // rent for PDAs
+    #[account(mut)]
+    pub depositor: Signer<'info>, // funds come from here
 
+    /// The pair's running deposit, whose `last_nonce` is the next item's nonce.
     #[account(
-        mut,
-        seeds = [VAULT_SEED],
-        bump = vault.bump
+        init_if_needed,
+        payer = payer,
+        space = 8 + Deposit::INIT_SPACE,
+        seeds = [DEPOSIT_SEED, depositor.key().as_ref(), username.as_bytes()],
+        bump
     )]
-    pub vault: Account<'info, Vault>,
+    pub running_deposit: Account<'info, Deposit>,
 
     #[account(
-        mut,
-        seeds = [DEPOSIT_SEED, depositor.key().as_ref(), deposit.username.as_bytes()],
+        init,
+        payer = payer,
+        space = 8 + Deposit::INIT_SPACE,
+        seeds = [
+            DEPOSIT_ITEM_SEED,
+            depositor.key().as_ref(),
+            username.as_bytes(),
+            nonce.to_le_bytes().as_ref()
+        ],
         bump
     )]
     pub deposit: Account<'info, Deposit>,
+
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimDeposit<'info> {
-    /// can be a new address
+pub struct RefundDeposit<'info> {
     #[account(mut)]
-    /// CHECK: Recipient can be any Solana account; lamport transfer is valid without further checks.
-    pub recipient: UncheckedAccount<'info>,
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.seed_prefix(),
+            depositor.key().as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+}
+
+#[derive(Accounts)]
+pub struct SetDepositExpiry<'info> {
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.seed_prefix(),
+            depositor.key().as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+}
+
+#[derive(Accounts)]
+pub struct SetDepositSchedule<'info> {
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.seed_prefix(),
+            depositor.key().as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+}
+
+#[derive(Accounts)]
+pub struct CancelVesting<'info> {
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.seed_prefix(),
+            depositor.key().as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+}
+
+#[derive(Accounts)]
+pub struct RefundExpiredDeposit<'info> {
+    #[account(mut)]
+    pub cranker: Signer<'info>,
+
+    /// CHECK: Only receives lamports; pinned to the deposit's owner.
+    #[account(mut, address = deposit.user @ ErrorCode::InvalidDepositor)]
+    pub depositor: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.seed_prefix(),
+            deposit.user.as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateDeposit<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>, // rent for the larger layout
 
     #[account(
         mut,
@@ -204,9 +886,29 @@ pub struct ClaimDeposit<'info> {
     )]
     pub vault: Account<'info, Vault>,
 
+    /// CHECK: Legacy deposit, too short to deserialize as `Deposit`; its
+    /// discriminator and address are checked by the handler.
+    #[account(mut, owner = crate::ID)]
+    pub deposit: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDeposit<'info> {
+    /// can be a new address
+    #[account(mut)]
+    /// CHECK: Recipient can be any Solana account; lamport transfer is valid without further checks.
+    pub recipient: UncheckedAccount<'info>,
+
     #[account(
         mut,
-        seeds = [DEPOSIT_SEED, deposit.user.as_ref(), deposit.username.as_bytes()],
+        seeds = [
+            deposit.seed_prefix(),
+            deposit.user.as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
         bump
     )]
     pub deposit: Account<'info, Deposit>,
@@ -214,12 +916,342 @@ pub struct ClaimDeposit<'info> {
     /// CHECK: We only read from instructions sysvar
     #[account(
         constraint = session.user_wallet == recipient.key() @ ErrorCode::InvalidRecipient,
-        constraint = session.verified @ ErrorCode::NotVerified
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified
     )]
     pub session: Account<'info, TelegramSession>,
+
+    /// Fees the session's wallet owes its relayer, required for sessions stored by one
+    /// even when nothing is owed, since the debt is read from it.
+    #[account(mut)]
+    pub relayer_usage: Option<Account<'info, RelayerUsage>>,
+    /// CHECK: Receives the settled fees; checked against `relayer_usage` by the verification program.
+    #[account(mut)]
+    pub relayer: Option<UncheckedAccount<'info>>,
+    pub verification_program: Option<Program<'info, TelegramVerification>>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct ClaimDepositTo<'info> {
+    /// Verified wallet authorizing the payout.
+    pub user_wallet: Signer<'info>,
+
+    /// CHECK: Any account named by the verified wallet; it only receives lamports.
+    #[account(mut)]
+    pub destination: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.seed_prefix(),
+            deposit.user.as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+
+    #[account(
+        constraint = session.user_wallet == user_wallet.key() @ ErrorCode::InvalidRecipient,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    /// Fees the session's wallet owes its relayer, required for sessions stored by one
+    /// even when nothing is owed, since the debt is read from it.
+    #[account(mut)]
+    pub relayer_usage: Option<Account<'info, RelayerUsage>>,
+    /// CHECK: Receives the settled fees; checked against `relayer_usage` by the verification program.
+    #[account(mut)]
+    pub relayer: Option<UncheckedAccount<'info>>,
+    pub verification_program: Option<Program<'info, TelegramVerification>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimDepositToPrivate<'info> {
+    /// Verified wallet authorizing the payout; pays for the wSOL account if needed.
+    #[account(mut)]
+    pub user_wallet: Signer<'info>,
+
+    /// Owner of the private-transfer deposit being credited.
+    pub destination: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            deposit.seed_prefix(),
+            deposit.user.as_ref(),
+            deposit.recipient_seed().as_ref(),
+            deposit.nonce_seed().as_ref()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, Deposit>,
+
+    #[account(
+        constraint = session.user_wallet == user_wallet.key() @ ErrorCode::InvalidRecipient,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    #[account(
+        init_if_needed,
+        payer = user_wallet,
+        associated_token::mint = native_mint,
+        associated_token::authority = destination,
+        associated_token::token_program = token_program,
+    )]
+    pub destination_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// CHECK: Private-transfer `Deposit` of `destination`; validated by that program.
+    #[account(
+        mut,
+        seeds = [PRIVATE_DEPOSIT_SEED, destination.key().as_ref(), native_mint.key().as_ref()],
+        bump,
+        seeds::program = private_transfer_program.key()
+    )]
+    pub private_deposit: UncheckedAccount<'info>,
+
+    /// CHECK: Private-transfer wSOL vault; validated by that program.
+    #[account(
+        mut,
+        seeds = [PRIVATE_VAULT_SEED, native_mint.key().as_ref()],
+        bump,
+        seeds::program = private_transfer_program.key()
+    )]
+    pub private_vault: UncheckedAccount<'info>,
+
+    /// CHECK: Vault's wSOL account; validated by that program.
+    #[account(mut)]
+    pub private_vault_token_account: UncheckedAccount<'info>,
+
+    #[account(address = native_mint::ID)]
+    pub native_mint: InterfaceAccount<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+
+    pub private_transfer_program: Program<'info, TelegramPrivateTransfer>,
+
+    /// Fees the session's wallet owes its relayer, required for sessions stored by one
+    /// even when nothing is owed, since the debt is read from it.
+    #[account(mut)]
+    pub relayer_usage: Option<Account<'info, RelayerUsage>>,
+    /// CHECK: Receives the settled fees; checked against `relayer_usage` by the verification program.
+    #[account(mut)]
+    pub relayer: Option<UncheckedAccount<'info>>,
+    pub verification_program: Option<Program<'info, TelegramVerification>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    /// CHECK: Recipient can be any Solana account; lamport transfer is valid without further checks.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    #[account(
+        constraint = session.user_wallet == recipient.key() @ ErrorCode::InvalidRecipient,
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    /// Fees the session's wallet owes its relayer, required for sessions stored by one
+    /// even when nothing is owed, since the debt is read from it.
+    #[account(mut)]
+    pub relayer_usage: Option<Account<'info, RelayerUsage>>,
+    /// CHECK: Receives the settled fees; checked against `relayer_usage` by the verification program.
+    #[account(mut)]
+    pub relayer: Option<UncheckedAccount<'info>>,
+    pub verification_program: Option<Program<'info, TelegramVerification>>,
+}
+
+#[derive(Accounts)]
+#[instruction(username: String)]
+pub struct DepositTokenForUsername<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>, // rent for PDAs
+    pub depositor: Signer<'info>, // tokens come from here
+
+    #[account(mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = depositor,
+        token::token_program = token_program
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    /// Per-mint vault, owned by itself.
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [TOKEN_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::mint = mint,
+        token::authority = vault_token_account,
+        token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + TokenDeposit::INIT_SPACE,
+        seeds = [
+            TOKEN_DEPOSIT_SEED,
+            depositor.key().as_ref(),
+            mint.key().as_ref(),
+            username.as_bytes()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, TokenDeposit>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RefundTokenDeposit<'info> {
+    pub depositor: Signer<'info>,
+
+    #[account(address = deposit.mint, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = depositor,
+        token::token_program = token_program
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            TOKEN_DEPOSIT_SEED,
+            depositor.key().as_ref(),
+            deposit.mint.as_ref(),
+            deposit.username.as_bytes()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, TokenDeposit>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub memo_program: Program<'info, Memo>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimTokenDeposit<'info> {
+    #[account(address = deposit.mint, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Any token account of the verified wallet for this mint.
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = session.user_wallet,
+        token::token_program = token_program
+    )]
+    pub recipient_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            TOKEN_DEPOSIT_SEED,
+            deposit.user.as_ref(),
+            deposit.mint.as_ref(),
+            deposit.username.as_bytes()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, TokenDeposit>,
+
+    #[account(
+        constraint = session.is_verified(Clock::get()?.unix_timestamp as u64) @ ErrorCode::NotVerified
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub memo_program: Program<'info, Memo>,
+}
+
+#[derive(Accounts)]
+pub struct SetTokenDepositExpiry<'info> {
+    pub depositor: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            TOKEN_DEPOSIT_SEED,
+            depositor.key().as_ref(),
+            deposit.mint.as_ref(),
+            deposit.username.as_bytes()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, TokenDeposit>,
+}
+
+#[derive(Accounts)]
+pub struct RefundExpiredTokenDeposit<'info> {
+    #[account(address = deposit.mint, mint::token_program = token_program)]
+    pub mint: InterfaceAccount<'info, Mint>,
+
+    /// Any token account of the depositor for this mint.
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = deposit.user,
+        token::token_program = token_program
+    )]
+    pub depositor_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [TOKEN_VAULT_SEED, mint.key().as_ref()],
+        bump,
+        token::token_program = token_program
+    )]
+    pub vault_token_account: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [
+            TOKEN_DEPOSIT_SEED,
+            deposit.user.as_ref(),
+            deposit.mint.as_ref(),
+            deposit.username.as_bytes()
+        ],
+        bump
+    )]
+    pub deposit: Account<'info, TokenDeposit>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+    pub memo_program: Program<'info, Memo>,
+}
+
 // ---------------- State ----------------
 
 /// A deposit account for a user and token mint.
@@ -230,10 +1262,174 @@ pub struct Deposit {
     #[max_len(32)]
     pub username: String,
     pub amount: u64,
+    /// Number of itemized deposits sent to `username`, i.e. the next item's nonce.
+    /// Only counted on the running deposit.
     pub last_nonce: u64,
+    /// Set for id-pinned deposits, which are claimable by this Telegram user id
+    /// regardless of the username it currently holds.
+    pub telegram_id: Option<u64>,
+    /// Unix time after which the deposit can no longer be claimed, only refunded.
+    pub expires_at: Option<i64>,
+    /// Whether the balance sits in this account rather than the legacy shared vault.
+    pub escrowed: bool,
+    /// Set for itemized deposits, which each live at their own nonce-indexed address.
+    pub nonce: Option<u64>,
+    pub created_at: i64,
+    #[max_len(MAX_MEMO_LEN)]
+    pub memo: String,
+    /// Display name the sender chose to show the recipient.
+    #[max_len(MAX_SENDER_NAME_LEN)]
+    pub sender_name: String,
+    /// Unix time before which nothing can be claimed; starts the vesting.
+    pub unlock_at: Option<i64>,
+    /// Unix time by which the balance has vested linearly from `unlock_at`.
+    pub vesting_end: Option<i64>,
+    /// Lamports claimed since the schedule was set.
+    pub claimed: u64,
+    pub bump: u8,
+    /// Lamports that had vested when the schedule was cancelled; only the
+    /// recipient's claims draw them down.
+    pub vested_locked: u64,
+}
+
+/// `Deposit` as stored before id-pinned, escrowed and scheduled deposits.
+#[derive(AnchorDeserialize)]
+struct LegacyDeposit {
+    user: Pubkey,
+    username: String,
+    amount: u64,
+    last_nonce: u64,
+}
+
+impl Deposit {
+    /// Reads a deposit account in the legacy layout, discriminator included.
+    ///
+    /// Legacy balances sit in the shared vault, so the result is not escrowed. An
+    /// account already sized for the current layout is rejected.
+    pub fn try_from_legacy(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() < 8 + Deposit::INIT_SPACE,
+            ErrorCode::DepositAlreadyMigrated
+        );
+        let mut rest = data
+            .strip_prefix(Deposit::DISCRIMINATOR)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch)?;
+        let legacy = LegacyDeposit::deserialize(&mut rest)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+
+        Ok(Self {
+            user: legacy.user,
+            username: legacy.username,
+            amount: legacy.amount,
+            last_nonce: legacy.last_nonce,
+            telegram_id: None,
+            expires_at: None,
+            escrowed: false,
+            nonce: None,
+            created_at: 0,
+            memo: String::new(),
+            sender_name: String::new(),
+            unlock_at: None,
+            vesting_end: None,
+            claimed: 0,
+            bump: 0,
+            vested_locked: 0,
+        })
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    pub fn is_vesting(&self) -> bool {
+        self.unlock_at.is_some()
+    }
+
+    /// Ends the schedule, locking what has vested by `now` for the recipient.
+    /// Returns the unvested rest, still to be paid back to the depositor.
+    pub fn cancel_schedule(&mut self, now: i64) -> u64 {
+        let vested = self.releasable(now);
+        self.unlock_at = None;
+        self.vesting_end = None;
+        self.claimed = 0;
+        self.vested_locked = vested;
+        self.amount - vested
+    }
+
+    /// Lamports the depositor may take back outside of `cancel_vesting`.
+    pub fn refundable(&self) -> u64 {
+        if self.is_vesting() {
+            return 0;
+        }
+        self.amount.saturating_sub(self.vested_locked)
+    }
+
+    /// Lamports the recipient may claim at `now` under the deposit's schedule.
+    pub fn releasable(&self, now: i64) -> u64 {
+        let Some(unlock_at) = self.unlock_at else {
+            return self.amount;
+        };
+        if now < unlock_at {
+            return 0;
+        }
+        let vested = match self.vesting_end {
+            Some(vesting_end) if now < vesting_end => {
+                // top-ups join the schedule, so vest over everything deposited since
+                let total = self.amount as u128 + self.claimed as u128;
+                let elapsed = (now - unlock_at) as u128;
+                (total * elapsed / (vesting_end - unlock_at) as u128) as u64
+            }
+            _ => return self.amount,
+        };
+        vested.saturating_sub(self.claimed).min(self.amount)
+    }
+
+    pub fn seed_prefix(&self) -> &'static [u8] {
+        match (self.telegram_id, self.nonce) {
+            (Some(_), _) => DEPOSIT_ID_SEED,
+            (None, Some(_)) => DEPOSIT_ITEM_SEED,
+            (None, None) => DEPOSIT_SEED,
+        }
+    }
+
+    pub fn recipient_seed(&self) -> Vec<u8> {
+        match self.telegram_id {
+            Some(telegram_id) => telegram_id.to_le_bytes().to_vec(),
+            None => self.username.as_bytes().to_vec(),
+        }
+    }
+
+    /// Trailing seed of itemized deposits. Empty otherwise, which leaves the
+    /// address of the other deposit kinds unchanged.
+    pub fn nonce_seed(&self) -> Vec<u8> {
+        match self.nonce {
+            Some(nonce) if self.telegram_id.is_none() => nonce.to_le_bytes().to_vec(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Tokens of one mint earmarked for a username, held in the mint's vault token account.
+#[account]
+#[derive(InitSpace)]
+pub struct TokenDeposit {
+    pub user: Pubkey,
+    #[max_len(32)]
+    pub username: String,
+    pub mint: Pubkey,
+    pub amount: u64,
+    /// After this time the deposit can no longer be claimed, only refunded.
+    pub expires_at: Option<i64>,
+}
+
+impl TokenDeposit {
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
 }
 
-/// A vault storing deposited SOL.
+/// The legacy shared vault for deposited SOL; deposits now escrow their own
+/// lamports and `migrate_deposit` drains what is left here.
 #[account]
 #[derive(InitSpace)]
 pub struct Vault {
@@ -241,6 +1437,332 @@ pub struct Vault {
     pub total_deposited: u64,
 }
 
+// ---------------- Events ----------------
+
+/// Emitted whenever SOL is escrowed into a deposit, top-ups included.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositCreated {
+    pub deposit: Pubkey,
+    pub depositor: Pubkey,
+    pub username: String,
+    pub telegram_id: Option<u64>,
+    pub nonce: Option<u64>,
+    pub amount: u64,
+    pub total: u64,
+}
+
+/// Emitted by `refund_deposit`, and by `refund_expired_deposit` with the
+/// cranker's cut in `crank_reward`.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositRefunded {
+    pub deposit: Pubkey,
+    pub depositor: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+    pub crank_reward: u64,
+}
+
+/// Emitted for every deposit paid out to a verified recipient.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepositClaimed {
+    pub deposit: Pubkey,
+    pub depositor: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+/// Emitted whenever tokens are escrowed into a token deposit, top-ups included.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenDepositCreated {
+    pub deposit: Pubkey,
+    pub depositor: Pubkey,
+    pub username: String,
+    pub mint: Pubkey,
+    /// What arrived in the vault, net of any Token-2022 transfer fee.
+    pub amount: u64,
+    pub total: u64,
+}
+
+/// Emitted by `refund_token_deposit` and `refund_expired_token_deposit`.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenDepositRefunded {
+    pub deposit: Pubkey,
+    pub depositor: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+/// Emitted for every token deposit paid out to a verified recipient.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenDepositClaimed {
+    pub deposit: Pubkey,
+    pub depositor: Pubkey,
+    /// Token account the tokens were paid into.
+    pub recipient: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub remaining: u64,
+}
+
+// ---------------- Helpers ----------------
+
+/// Checks that `session` may claim from `deposit` at `now`.
+fn require_claimable(deposit: &Deposit, session: &TelegramSession, now: i64) -> Result<()> {
+    require!(!deposit.is_expired(now), ErrorCode::DepositExpired);
+    match deposit.telegram_id {
+        Some(telegram_id) => require!(
+            session.telegram_id == telegram_id,
+            ErrorCode::InvalidTelegramId
+        ),
+        None => require!(
+            session.username == deposit.username,
+            ErrorCode::InvalidUsername
+        ),
+    }
+    Ok(())
+}
+
+/// What `session`'s wallet still owes the relayer that stored it, read from `usage`.
+fn owed_relayer_fee(
+    session: &TelegramSession,
+    usage: Option<&Account<RelayerUsage>>,
+) -> Result<u64> {
+    let Some(relayer) = session.relayer else {
+        return Ok(0);
+    };
+    let usage = usage.ok_or(ErrorCode::MissingRelayerUsage)?;
+    require!(
+        usage.relayer == relayer && usage.user_wallet == session.user_wallet,
+        ErrorCode::InvalidRelayerUsage
+    );
+    Ok(usage.owed_lamports)
+}
+
+/// Pays the relayer fees `session`'s wallet owes out of a claim of `amount` and
+/// returns what is left for the recipient.
+///
+/// The fees are lamports, so only SOL claims settle them. Token claims leave them owed
+/// until a later SOL claim or `reimburse_relayer`, and the session can't close until then.
+fn settle_relayer_fee<'info>(
+    deposit: &mut Account<'info, Deposit>,
+    session: &TelegramSession,
+    usage: Option<&mut Account<'info, RelayerUsage>>,
+    relayer: Option<&UncheckedAccount<'info>>,
+    verification_program: Option<&Program<'info, TelegramVerification>>,
+    amount: u64,
+    now: i64,
+) -> Result<u64> {
+    let fee = owed_relayer_fee(session, usage.as_deref())?.min(amount);
+    if fee == 0 {
+        return Ok(amount);
+    }
+    let (Some(usage), Some(relayer), Some(verification_program)) =
+        (usage, relayer, verification_program)
+    else {
+        return err!(ErrorCode::MissingRelayerUsage);
+    };
+
+    // move lamports: deposit -> usage -> relayer
+    claim_from_deposit(deposit, &usage.to_account_info(), fee, now)?;
+    telegram_verification::cpi::settle_relayer_fee(CpiContext::new(
+        verification_program.to_account_info(),
+        SettleRelayerFee {
+            relayer: relayer.to_account_info(),
+            usage: usage.to_account_info(),
+        },
+    ))?;
+    // a batch claim settles again from the same usage
+    usage.reload()?;
+    Ok(amount - fee)
+}
+
+fn emit_claimed(deposit: &Account<Deposit>, recipient: &AccountInfo, amount: u64) {
+    emit!(DepositClaimed {
+        deposit: deposit.key(),
+        depositor: deposit.user,
+        recipient: recipient.key(),
+        amount,
+        remaining: deposit.amount,
+    });
+}
+
+/// Initializes the running deposit of `depositor` for `username` on first use.
+fn init_running_deposit(
+    deposit: &mut Deposit,
+    depositor: Pubkey,
+    username: &str,
+    bump: u8,
+) -> Result<()> {
+    if deposit.user != Pubkey::default() {
+        return Ok(());
+    }
+
+    deposit.user = depositor;
+    deposit.username = username.to_string();
+    deposit.amount = 0;
+    deposit.last_nonce = 0;
+    deposit.telegram_id = None;
+    deposit.expires_at = None;
+    deposit.escrowed = true;
+    deposit.nonce = None;
+    deposit.created_at = Clock::get()?.unix_timestamp;
+    deposit.memo = String::new();
+    deposit.sender_name = String::new();
+    deposit.unlock_at = None;
+    deposit.vesting_end = None;
+    deposit.claimed = 0;
+    deposit.vested_locked = 0;
+    deposit.bump = bump;
+    Ok(())
+}
+
+/// Moves `amount` lamports from the depositor into the deposit account and books them.
+fn fund_deposit<'info>(
+    deposit: &mut Account<'info, Deposit>,
+    depositor: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    amount: u64,
+) -> Result<()> {
+    require!(depositor.key() == deposit.user, ErrorCode::InvalidDepositor);
+    require!(deposit.escrowed, ErrorCode::DepositNotMigrated);
+    // re-funding an expired deposit would let it outlive its expiry
+    require!(
+        !deposit.is_expired(Clock::get()?.unix_timestamp),
+        ErrorCode::DepositExpired
+    );
+
+    // depositor -> deposit lamports
+    transfer(
+        CpiContext::new(
+            system_program.to_account_info(),
+            Transfer {
+                from: depositor.to_account_info(),
+                to: deposit.to_account_info(),
+            },
+        ),
+        amount,
+    )?;
+
+    // book-keeping
+    deposit.amount = deposit
+        .amount
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+
+    emit!(DepositCreated {
+        deposit: deposit.key(),
+        depositor: deposit.user,
+        username: deposit.username.clone(),
+        telegram_id: deposit.telegram_id,
+        nonce: deposit.nonce,
+        amount,
+        total: deposit.amount,
+    });
+    Ok(())
+}
+
+/// Pays a recipient's claim, limited to what the deposit's schedule has released.
+fn claim_from_deposit<'info>(
+    deposit: &mut Account<'info, Deposit>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+    now: i64,
+) -> Result<()> {
+    require!(amount <= deposit.releasable(now), ErrorCode::DepositLocked);
+    deposit.claimed = deposit
+        .claimed
+        .checked_add(amount)
+        .ok_or(ErrorCode::Overflow)?;
+    deposit.vested_locked = deposit.vested_locked.saturating_sub(amount);
+    pay_from_deposit(deposit, to, amount)
+}
+
+/// Pays `amount` lamports escrowed in `deposit` out to `to`, keeping the deposit rent-exempt.
+fn pay_from_deposit<'info>(
+    deposit: &mut Account<'info, Deposit>,
+    to: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<()> {
+    require!(deposit.escrowed, ErrorCode::DepositNotMigrated);
+    deposit.amount = deposit
+        .amount
+        .checked_sub(amount)
+        .ok_or(ErrorCode::InsufficientDeposit)?;
+
+    let deposit_ai = deposit.to_account_info();
+    let rent_min = Rent::get()?.minimum_balance(deposit_ai.data_len());
+    let available = deposit_ai.lamports().saturating_sub(rent_min);
+    require!(available >= amount, ErrorCode::InsufficientDeposit);
+
+    **deposit_ai.try_borrow_mut_lamports()? -= amount;
+    **to.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+/// Pays `amount` out of a per-mint vault, adding a memo first when the
+/// destination is a Token-2022 account that requires incoming memos.
+fn withdraw_from_token_vault<'info>(
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    mint: &InterfaceAccount<'info, Mint>,
+    to: &AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    memo_program: &Program<'info, Memo>,
+    vault_bump: u8,
+    amount: u64,
+) -> Result<()> {
+    require!(vault.amount >= amount, ErrorCode::InsufficientVault);
+
+    if requires_incoming_memo(to)? {
+        build_memo(
+            CpiContext::new(memo_program.to_account_info(), BuildMemo {}),
+            WITHDRAW_MEMO,
+        )?;
+    }
+
+    let mint_key = mint.key();
+    let signer_seeds: &[&[&[u8]]] = &[&[TOKEN_VAULT_SEED, mint_key.as_ref(), &[vault_bump]]];
+    transfer_checked(
+        CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            TransferChecked {
+                from: vault.to_account_info(),
+                mint: mint.to_account_info(),
+                to: to.clone(),
+                authority: vault.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        amount,
+        mint.decimals,
+    )
+}
+
+/// Rejects mints the shared per-mint vault cannot safely hold, see `supported_mints`.
+fn validate_mint(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    require!(
+        supported_mints::is_supported_mint(&mint.to_account_info())?,
+        ErrorCode::UnsupportedMint
+    );
+    Ok(())
+}
+
+fn requires_incoming_memo(token_account: &AccountInfo) -> Result<bool> {
+    if *token_account.owner != Token2022::id() {
+        return Ok(false);
+    }
+    let data = token_account.try_borrow_data()?;
+    let state = StateWithExtensions::<SplTokenAccount>::unpack(&data)?;
+    Ok(memo_required(&state))
+}
+
 // ---------------- Error Codes ----------------
 #[error_code]
 pub enum ErrorCode {
@@ -264,4 +1786,40 @@ pub enum ErrorCode {
     InvalidRecipient,
     #[msg("Invalid Depositor")]
     InvalidDepositor,
+    #[msg("Invalid Telegram Id")]
+    InvalidTelegramId,
+    #[msg("Deposit Expired")]
+    DepositExpired,
+    #[msg("Deposit Not Expired")]
+    DepositNotExpired,
+    #[msg("Invalid Expiry")]
+    InvalidExpiry,
+    #[msg("Deposit Not Migrated")]
+    DepositNotMigrated,
+    #[msg("Deposit Already Migrated")]
+    DepositAlreadyMigrated,
+    #[msg("Invalid Memo")]
+    InvalidMemo,
+    #[msg("Invalid Sender Name")]
+    InvalidSenderName,
+    #[msg("Invalid Batch")]
+    InvalidBatch,
+    #[msg("Invalid Deposit")]
+    InvalidDeposit,
+    #[msg("Deposit Locked")]
+    DepositLocked,
+    #[msg("Deposit Vesting")]
+    DepositVesting,
+    #[msg("Invalid Schedule")]
+    InvalidSchedule,
+    #[msg("Missing Relayer Usage")]
+    MissingRelayerUsage,
+    #[msg("Invalid Relayer Usage")]
+    InvalidRelayerUsage,
+    #[msg("Relayer Fee Outstanding")]
+    RelayerFeeOutstanding,
+    #[msg("Unsupported Mint")]
+    UnsupportedMint,
+    #[msg("Invalid Nonce")]
+    InvalidNonce,
 }
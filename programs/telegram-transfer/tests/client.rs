@@ -0,0 +1,335 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, Discriminator, Event};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use base64::Engine;
+use telegram_transfer::client::{
+    claim_deposit_ix, claim_deposit_to_ix, claim_deposit_to_private_ix, claim_many_ix,
+    claim_token_deposit_ix, decode_deposit, deposit_address, deposit_for_username_ix,
+    deposit_token_for_username_ix, find_deposit_item_pda, find_deposit_item_pdas, find_deposit_pda,
+    find_private_deposit_pda, find_relayer_usage_pda, find_session_pda,
+    find_telegram_id_deposit_pda, find_token_deposit_pda, find_token_vault_pda, refund_deposit_ix,
+    refund_token_deposit_ix, TransferEvent,
+};
+use telegram_transfer::{
+    instruction, Deposit, DepositClaimed, DepositCreated, DepositRefunded, TokenDepositClaimed,
+    TokenDepositCreated, TokenDepositRefunded,
+};
+
+const USERNAME: &str = "dig133713337";
+
+fn deposit(telegram_id: Option<u64>, nonce: Option<u64>) -> Deposit {
+    Deposit {
+        user: Pubkey::new_from_array([7; 32]),
+        username: if telegram_id.is_some() {
+            String::new()
+        } else {
+            USERNAME.to_string()
+        },
+        amount: 1_000_000,
+        last_nonce: 0,
+        telegram_id,
+        expires_at: Some(1763684800),
+        escrowed: true,
+        nonce,
+        created_at: 1763598375,
+        memo: "gift".to_string(),
+        sender_name: "Alice".to_string(),
+        unlock_at: None,
+        vesting_end: None,
+        claimed: 0,
+        bump: 255,
+        vested_locked: 0,
+    }
+}
+
+fn log_line(data: &[u8]) -> String {
+    format!(
+        "Program data: {}",
+        base64::engine::general_purpose::STANDARD.encode(data)
+    )
+}
+
+#[test]
+fn deposit_address_matches_the_pda_helpers() {
+    let depositor = Pubkey::new_from_array([7; 32]);
+
+    assert_eq!(
+        deposit_address(&deposit(None, None)),
+        find_deposit_pda(&depositor, USERNAME)
+    );
+    assert_eq!(
+        deposit_address(&deposit(Some(8138797767), None)),
+        find_telegram_id_deposit_pda(&depositor, 8138797767)
+    );
+    assert_eq!(
+        deposit_address(&deposit(None, Some(3))),
+        find_deposit_item_pda(&depositor, USERNAME, 3)
+    );
+    assert_ne!(
+        find_deposit_item_pda(&depositor, USERNAME, 3),
+        find_deposit_item_pda(&depositor, USERNAME, 4)
+    );
+    assert_eq!(
+        find_deposit_item_pdas(&depositor, USERNAME, 2),
+        [
+            find_deposit_item_pda(&depositor, USERNAME, 0),
+            find_deposit_item_pda(&depositor, USERNAME, 1)
+        ]
+    );
+}
+
+#[test]
+fn builds_instructions_for_the_program() {
+    let payer = Pubkey::new_from_array([1; 32]);
+    let depositor = Pubkey::new_from_array([2; 32]);
+    let deposit = find_deposit_pda(&depositor, USERNAME);
+
+    let ix = deposit_for_username_ix(payer, depositor, USERNAME, 42);
+    assert_eq!(ix.program_id, telegram_transfer::ID);
+    assert_eq!(
+        ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>(),
+        vec![payer, depositor, deposit, anchor_lang::system_program::ID]
+    );
+    assert!(ix.accounts[0].is_signer && ix.accounts[1].is_signer);
+    assert!(ix
+        .data
+        .starts_with(instruction::DepositForUsername::DISCRIMINATOR));
+
+    let ix = refund_deposit_ix(depositor, deposit, 42);
+    assert_eq!(
+        ix.data,
+        [
+            instruction::RefundDeposit::DISCRIMINATOR,
+            &42u64.to_le_bytes()
+        ]
+        .concat()
+    );
+
+    let recipient = Pubkey::new_from_array([3; 32]);
+    let ix = claim_deposit_ix(recipient, deposit, 42, None);
+    assert_eq!(ix.accounts[2].pubkey, find_session_pda(&recipient));
+}
+
+#[test]
+fn token_deposit_instructions_use_the_mint_vault_and_associated_token_accounts() {
+    let payer = Pubkey::new_from_array([1; 32]);
+    let depositor = Pubkey::new_from_array([2; 32]);
+    let mint = Pubkey::new_from_array([5; 32]);
+    let token_program = anchor_spl::token_2022::ID;
+    let deposit = find_token_deposit_pda(&depositor, &mint, USERNAME);
+    let vault = find_token_vault_pda(&mint);
+    let depositor_ata =
+        get_associated_token_address_with_program_id(&depositor, &mint, &token_program);
+
+    let ix = deposit_token_for_username_ix(payer, depositor, mint, token_program, USERNAME, 42);
+    assert_eq!(
+        ix.accounts.iter().map(|m| m.pubkey).collect::<Vec<_>>(),
+        vec![
+            payer,
+            depositor,
+            mint,
+            depositor_ata,
+            vault,
+            deposit,
+            token_program,
+            anchor_lang::system_program::ID
+        ]
+    );
+    assert!(ix.accounts[0].is_signer && ix.accounts[1].is_signer);
+
+    let ix = refund_token_deposit_ix(depositor, deposit, mint, token_program, 42);
+    assert_eq!(ix.accounts[2].pubkey, depositor_ata);
+    assert!(ix.accounts[0].is_signer);
+
+    let user_wallet = Pubkey::new_from_array([3; 32]);
+    let ix = claim_token_deposit_ix(user_wallet, deposit, mint, token_program, 42);
+    assert_eq!(
+        ix.accounts[1].pubkey,
+        get_associated_token_address_with_program_id(&user_wallet, &mint, &token_program)
+    );
+    assert_eq!(ix.accounts[4].pubkey, find_session_pda(&user_wallet));
+    assert!(ix.accounts.iter().all(|m| !m.is_signer));
+}
+
+#[test]
+fn claims_of_relayed_sessions_settle_the_relayer() {
+    let recipient = Pubkey::new_from_array([3; 32]);
+    let relayer = Pubkey::new_from_array([6; 32]);
+    let deposit = find_deposit_pda(&Pubkey::new_from_array([2; 32]), USERNAME);
+
+    let ix = claim_deposit_ix(recipient, deposit, 42, Some(relayer));
+    assert_eq!(
+        ix.accounts[3..6]
+            .iter()
+            .map(|m| m.pubkey)
+            .collect::<Vec<_>>(),
+        vec![
+            find_relayer_usage_pda(&relayer, &recipient),
+            relayer,
+            telegram_verification::ID
+        ]
+    );
+    assert!(ix.accounts[3].is_writable && ix.accounts[4].is_writable);
+
+    // without a relayer the optional accounts are left out
+    let ix = claim_deposit_ix(recipient, deposit, 42, None);
+    assert!(ix.accounts[3..6]
+        .iter()
+        .all(|m| m.pubkey == telegram_transfer::ID && !m.is_writable));
+}
+
+#[test]
+fn claims_to_another_destination_use_the_signer_session() {
+    let user_wallet = Pubkey::new_from_array([3; 32]);
+    let destination = Pubkey::new_from_array([4; 32]);
+    let deposit = find_deposit_pda(&Pubkey::new_from_array([2; 32]), USERNAME);
+
+    let ix = claim_deposit_to_ix(user_wallet, destination, deposit, 42, None);
+    assert_eq!(
+        ix.accounts[..4]
+            .iter()
+            .map(|m| m.pubkey)
+            .collect::<Vec<_>>(),
+        vec![
+            user_wallet,
+            destination,
+            deposit,
+            find_session_pda(&user_wallet)
+        ]
+    );
+    assert!(ix.accounts[0].is_signer && !ix.accounts[1].is_signer);
+    assert!(ix.accounts[1].is_writable);
+
+    let ix = claim_deposit_to_private_ix(user_wallet, destination, deposit, 42, None);
+    assert!(ix.accounts[0].is_signer && ix.accounts[1].is_signer);
+    assert_eq!(ix.accounts[3].pubkey, find_session_pda(&user_wallet));
+    assert_eq!(
+        ix.accounts[5].pubkey,
+        find_private_deposit_pda(&destination)
+    );
+    assert_eq!(ix.accounts[11].pubkey, telegram_private_transfer::ID);
+}
+
+#[test]
+fn claim_many_appends_writable_deposits() {
+    let recipient = Pubkey::new_from_array([3; 32]);
+    let deposits = [
+        Pubkey::new_from_array([4; 32]),
+        Pubkey::new_from_array([5; 32]),
+    ];
+
+    let ix = claim_many_ix(recipient, &deposits, None, None);
+    assert_eq!(ix.accounts.len(), 7);
+    assert_eq!(ix.accounts[1].pubkey, find_session_pda(&recipient));
+    for (meta, deposit) in ix.accounts[5..].iter().zip(deposits) {
+        assert_eq!(meta.pubkey, deposit);
+        assert!(meta.is_writable && !meta.is_signer);
+    }
+    assert_eq!(
+        ix.data,
+        [instruction::ClaimMany::DISCRIMINATOR, &[0]].concat()
+    );
+}
+
+#[test]
+fn decodes_deposit_accounts() {
+    let original = deposit(None, Some(3));
+    let mut data = Vec::new();
+    original.try_serialize(&mut data).unwrap();
+    // accounts are allocated at full size, so trailing padding must be ignored
+    data.resize(8 + <Deposit as anchor_lang::Space>::INIT_SPACE, 0);
+
+    let decoded = decode_deposit(&data).unwrap();
+    assert_eq!(decoded.user, original.user);
+    assert_eq!(decoded.username, USERNAME);
+    assert_eq!(decoded.nonce, Some(3));
+    assert_eq!(decoded.memo, "gift");
+    assert_eq!(decoded.sender_name, "Alice");
+
+    assert!(decode_deposit(&data[8..]).is_err());
+}
+
+#[test]
+fn decodes_each_event_from_its_log_line() {
+    let created = DepositCreated {
+        deposit: Pubkey::new_from_array([1; 32]),
+        depositor: Pubkey::new_from_array([2; 32]),
+        username: USERNAME.to_string(),
+        telegram_id: None,
+        nonce: Some(3),
+        amount: 5,
+        total: 10,
+    };
+    let refunded = DepositRefunded {
+        deposit: Pubkey::new_from_array([1; 32]),
+        depositor: Pubkey::new_from_array([2; 32]),
+        amount: 4,
+        remaining: 0,
+        crank_reward: 1,
+    };
+    let claimed = DepositClaimed {
+        deposit: Pubkey::new_from_array([1; 32]),
+        depositor: Pubkey::new_from_array([2; 32]),
+        recipient: Pubkey::new_from_array([3; 32]),
+        amount: 5,
+        remaining: 5,
+    };
+
+    let logs = [
+        "Program 4ewpzEPF5xrVAHeRkoe7XS1yKFGQBekD7PgFwEz9SaxY invoke [1]".to_string(),
+        log_line(&created.data()),
+        log_line(&claimed.data()),
+        "Program data: not-base64!".to_string(),
+        log_line(&refunded.data()),
+    ];
+    assert_eq!(
+        TransferEvent::from_logs(logs.iter().map(String::as_str)),
+        vec![
+            TransferEvent::Created(created),
+            TransferEvent::Claimed(claimed),
+            TransferEvent::Refunded(refunded),
+        ]
+    );
+}
+
+#[test]
+fn decodes_token_deposit_events() {
+    let mint = Pubkey::new_from_array([9; 32]);
+    let created = TokenDepositCreated {
+        deposit: Pubkey::new_from_array([1; 32]),
+        depositor: Pubkey::new_from_array([2; 32]),
+        username: USERNAME.to_string(),
+        mint,
+        amount: 99,
+        total: 99,
+    };
+    let claimed = TokenDepositClaimed {
+        deposit: Pubkey::new_from_array([1; 32]),
+        depositor: Pubkey::new_from_array([2; 32]),
+        recipient: Pubkey::new_from_array([3; 32]),
+        mint,
+        amount: 40,
+        remaining: 59,
+    };
+    let refunded = TokenDepositRefunded {
+        deposit: Pubkey::new_from_array([1; 32]),
+        depositor: Pubkey::new_from_array([2; 32]),
+        mint,
+        amount: 59,
+        remaining: 0,
+    };
+
+    let logs = [
+        log_line(&created.data()),
+        log_line(&claimed.data()),
+        log_line(&refunded.data()),
+    ];
+    assert_eq!(
+        TransferEvent::from_logs(logs.iter().map(String::as_str)),
+        vec![
+            TransferEvent::TokenCreated(created),
+            TransferEvent::TokenClaimed(claimed),
+            TransferEvent::TokenRefunded(refunded),
+        ]
+    );
+}
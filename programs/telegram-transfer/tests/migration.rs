@@ -0,0 +1,50 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, AnchorSerialize, Discriminator, Space};
+use telegram_transfer::{Deposit, ErrorCode};
+
+/// Size of a legacy deposit: discriminator, user, 32-byte username, amount, last_nonce.
+const LEGACY_DEPOSIT_SPACE: usize = 92;
+
+/// A legacy deposit for `username`; the username always had 32 bytes allocated.
+fn legacy_deposit_account(username: &str) -> Vec<u8> {
+    let fields = (
+        Pubkey::new_from_array([7; 32]),
+        username.to_string(),
+        1_000_000u64,
+        3u64,
+    );
+    let mut data = Deposit::DISCRIMINATOR.to_vec();
+    data.extend(fields.try_to_vec().unwrap());
+    data.resize(LEGACY_DEPOSIT_SPACE, 0);
+    data
+}
+
+#[test]
+fn legacy_deposits_keep_their_balance_in_the_vault() {
+    let deposit = Deposit::try_from_legacy(&legacy_deposit_account("dig133713337")).unwrap();
+
+    assert_eq!(deposit.user, Pubkey::new_from_array([7; 32]));
+    assert_eq!(deposit.username, "dig133713337");
+    assert_eq!(deposit.amount, 1_000_000);
+    assert!(!deposit.escrowed);
+    assert!(!deposit.is_vesting());
+    assert_eq!(deposit.telegram_id, None);
+    assert_eq!(deposit.nonce, None);
+
+    let mut data = Vec::new();
+    deposit.try_serialize(&mut data).unwrap();
+    data.resize(8 + Deposit::INIT_SPACE, 0);
+    let err = Deposit::try_from_legacy(&data).err().unwrap();
+    assert_eq!(err, ErrorCode::DepositAlreadyMigrated.into());
+}
+
+#[test]
+fn full_length_usernames_fill_the_legacy_account() {
+    let username = "a".repeat(32);
+    let data = legacy_deposit_account(&username);
+    assert_eq!(data.len(), LEGACY_DEPOSIT_SPACE);
+
+    let deposit = Deposit::try_from_legacy(&data).unwrap();
+    assert_eq!(deposit.username, username);
+    assert_eq!(deposit.amount, 1_000_000);
+}
@@ -0,0 +1,78 @@
+use anchor_lang::prelude::Pubkey;
+use telegram_transfer::Deposit;
+
+const UNLOCK_AT: i64 = 1_000;
+const VESTING_END: i64 = 2_000;
+
+fn deposit(amount: u64, unlock_at: Option<i64>, vesting_end: Option<i64>) -> Deposit {
+    Deposit {
+        user: Pubkey::new_from_array([7; 32]),
+        username: "dig133713337".to_string(),
+        amount,
+        last_nonce: 0,
+        telegram_id: None,
+        expires_at: None,
+        escrowed: true,
+        nonce: None,
+        created_at: 0,
+        memo: String::new(),
+        sender_name: String::new(),
+        unlock_at,
+        vesting_end,
+        claimed: 0,
+        bump: 255,
+        vested_locked: 0,
+    }
+}
+
+#[test]
+fn unscheduled_deposits_release_everything() {
+    let deposit = deposit(1_000, None, None);
+    assert!(!deposit.is_vesting());
+    assert_eq!(deposit.releasable(0), 1_000);
+}
+
+#[test]
+fn unlock_without_vesting_is_a_cliff() {
+    let deposit = deposit(1_000, Some(UNLOCK_AT), None);
+    assert!(deposit.is_vesting());
+    assert_eq!(deposit.releasable(UNLOCK_AT - 1), 0);
+    assert_eq!(deposit.releasable(UNLOCK_AT), 1_000);
+}
+
+#[test]
+fn vesting_releases_linearly_net_of_claims() {
+    let mut deposit = deposit(1_000, Some(UNLOCK_AT), Some(VESTING_END));
+    assert_eq!(deposit.releasable(UNLOCK_AT - 1), 0);
+    assert_eq!(deposit.releasable(UNLOCK_AT), 0);
+    assert_eq!(deposit.releasable(1_250), 250);
+
+    // claiming the vested quarter leaves the schedule over the original total
+    deposit.amount -= 250;
+    deposit.claimed += 250;
+    assert_eq!(deposit.releasable(1_250), 0);
+    assert_eq!(deposit.releasable(1_500), 250);
+    assert_eq!(deposit.releasable(VESTING_END), 750);
+    assert_eq!(deposit.releasable(i64::MAX), 750);
+}
+
+#[test]
+fn cancelling_keeps_vested_funds_out_of_refunds() {
+    let mut deposit = deposit(1_000, Some(UNLOCK_AT), Some(VESTING_END));
+    assert_eq!(deposit.refundable(), 0);
+
+    // halfway through, the depositor gets the unvested half back
+    let unvested = deposit.cancel_schedule(1_500);
+    assert_eq!(unvested, 500);
+    deposit.amount -= unvested;
+    assert!(!deposit.is_vesting());
+    assert_eq!(deposit.vested_locked, 500);
+
+    // the vested half is the recipient's: claimable, but not refundable
+    assert_eq!(deposit.releasable(1_500), 500);
+    assert_eq!(deposit.refundable(), 0);
+
+    // later top-ups stay refundable
+    deposit.amount += 100;
+    assert_eq!(deposit.refundable(), 100);
+}
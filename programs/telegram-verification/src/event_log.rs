@@ -0,0 +1,20 @@
+//! Decoding of Anchor events from transaction logs, shared with `telegram_transfer`.
+
+use anchor_lang::{AnchorDeserialize, Discriminator};
+use base64::Engine;
+
+const PROGRAM_DATA_LOG_PREFIX: &str = "Program data: ";
+
+/// Bytes `emit!` logged on a `Program data: ` line, `None` for any other line.
+pub fn program_data(line: &str) -> Option<Vec<u8>> {
+    let data = line.strip_prefix(PROGRAM_DATA_LOG_PREFIX)?;
+    base64::engine::general_purpose::STANDARD
+        .decode(data.trim_end())
+        .ok()
+}
+
+/// Decodes `data` as event `E` if it starts with `E`'s discriminator.
+pub fn decode_event<E: Discriminator + AnchorDeserialize>(data: &[u8]) -> Option<E> {
+    let mut body = data.strip_prefix(E::DISCRIMINATOR)?;
+    E::deserialize(&mut body).ok()
+}
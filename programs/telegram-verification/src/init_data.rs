@@ -0,0 +1,441 @@
+//! Telegram Mini App initData rules shared by the program and off-chain code.
+//!
+//! The parsing here is what `store` runs on-chain. Host builds additionally get
+//! helpers to build the Ed25519 precompile instruction and to check the Telegram
+//! signature locally, so services and the CLI can reject bad payloads before
+//! sending a transaction.
+
+use base64::Engine;
+use sha2::{Digest, Sha256};
+
+use crate::telegram_user::{parse_telegram_user, TelegramUser};
+
+pub const MAX_VALIDATION_LEN: usize = 768;
+pub const MAX_INIT_DATA_LEN: usize = 1024;
+pub const MIN_USERNAME_LEN: usize = 5;
+pub const MAX_USERNAME_LEN: usize = 32;
+/// Longest data-check-string `store_and_verify` fits in one transaction, with
+/// a separate payer, a 32-byte username and both optional accounts passed.
+/// Longer initData has to go through `store` and `verify_telegram_init_data`.
+pub const MAX_STORE_AND_VERIFY_LEN: usize = 465;
+/// Offset of the data-check-string in `store_and_verify` instruction data,
+/// after the discriminator, `bot_id` and the vec length.
+pub const STORE_AND_VERIFY_DATA_CHECK_STRING_OFFSET: u16 = 8 + 8 + 4;
+/// Allowed drift between Telegram's `auth_date` and the verifying clock.
+pub const MAX_AUTH_CLOCK_SKEW_SECS: u64 = 60;
+
+const AUTH_DATE_PREFIX: &str = "\nauth_date=";
+const WEB_APP_DATA_SUFFIX: &str = ":WebAppData";
+
+pub const ED25519_HEADER_LEN: usize = 2; // [sig_count: u8, padding: u8]
+pub const ED25519_OFFSETS_LEN: usize = 14; // 7 * u16 (LE)
+pub const PUBKEY_LEN: usize = 32;
+pub const SIG_LEN: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InitDataError {
+    /// Raw initData or the rebuilt data-check-string is too long.
+    TooLong,
+    /// Not a well-formed initData query string.
+    Malformed,
+    InvalidUsername,
+    InvalidAuthDate,
+    /// `auth_date` is older than the accepted maximum age.
+    Expired,
+    /// The `signature` field is missing, undecodable or does not verify.
+    InvalidSignature,
+}
+
+/// initData as accepted by `store`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitData {
+    /// The data-check-string Telegram signs, `<bot_id>:WebAppData\n<sorted fields>`.
+    pub data_check_string: Vec<u8>,
+    pub user: TelegramUser,
+    /// `user.username`, validated and lowercased.
+    pub username: String,
+    pub auth_date: u64,
+}
+
+impl InitData {
+    /// sha256 of the data-check-string, the seed of the consumed-payload PDA.
+    pub fn payload_hash(&self) -> [u8; 32] {
+        Sha256::digest(&self.data_check_string).into()
+    }
+}
+
+/// Parses raw `Telegram.WebApp.initData` issued for `bot_id`.
+///
+/// Freshness of `auth_date` is not checked here, see `check_auth_date`.
+pub fn parse_init_data(bot_id: u64, init_data: &[u8]) -> Result<InitData, InitDataError> {
+    if init_data.len() > MAX_INIT_DATA_LEN {
+        return Err(InitDataError::TooLong);
+    }
+
+    parse_signed_fields(build_data_check_string(bot_id, init_data)?)
+}
+
+/// Parses a data-check-string as Telegram signs it for `bot_id`, what
+/// `store_and_verify` takes in place of raw initData.
+///
+/// Fields must be sorted by key, without duplicates, `hash` or `signature`,
+/// exactly as `build_data_check_string` lays them out.
+pub fn parse_data_check_string(
+    bot_id: u64,
+    data_check_string: &[u8],
+) -> Result<InitData, InitDataError> {
+    let prefix = format!("{bot_id}{WEB_APP_DATA_SUFFIX}");
+    let fields = data_check_string
+        .strip_prefix(prefix.as_bytes())
+        .and_then(|fields| fields.strip_prefix(b"\n"))
+        .ok_or(InitDataError::Malformed)?;
+
+    let mut previous: Option<&[u8]> = None;
+    for field in fields.split(|b| *b == b'\n') {
+        let eq = field
+            .iter()
+            .position(|b| *b == b'=')
+            .ok_or(InitDataError::Malformed)?;
+        let key = &field[..eq];
+        if key.is_empty()
+            || key == b"hash"
+            || key == b"signature"
+            || previous.is_some_and(|previous| previous >= key)
+        {
+            return Err(InitDataError::Malformed);
+        }
+        previous = Some(key);
+    }
+
+    parse_signed_fields(data_check_string.to_vec())
+}
+
+fn parse_signed_fields(data_check_string: Vec<u8>) -> Result<InitData, InitDataError> {
+    if data_check_string.len() > MAX_VALIDATION_LEN {
+        return Err(InitDataError::TooLong);
+    }
+
+    let user = parse_telegram_user(extract_user_payload(&data_check_string)?.as_bytes())
+        .map_err(|_| InitDataError::Malformed)?;
+    let username = normalize_username(user.username.as_deref())?;
+    let auth_date = extract_auth_date(&data_check_string)?;
+
+    Ok(InitData {
+        data_check_string,
+        user,
+        username,
+        auth_date,
+    })
+}
+
+/// Rejects an `auth_date` from the future (beyond clock skew) or older than `max_auth_age_secs`.
+pub fn check_auth_date(
+    auth_date: u64,
+    now: u64,
+    max_auth_age_secs: u64,
+) -> Result<(), InitDataError> {
+    if auth_date > now.saturating_add(MAX_AUTH_CLOCK_SKEW_SECS) {
+        return Err(InitDataError::InvalidAuthDate);
+    }
+    if now.saturating_sub(auth_date) > max_auth_age_secs {
+        return Err(InitDataError::Expired);
+    }
+    Ok(())
+}
+
+/// Rebuilds the data-check-string Telegram signs for third-party validation.
+///
+/// Fields are URL-decoded, `hash` and `signature` are dropped and the rest is
+/// sorted by key and joined with `\n` after `<bot_id>:WebAppData`.
+pub fn build_data_check_string(bot_id: u64, init_data: &[u8]) -> Result<Vec<u8>, InitDataError> {
+    let mut fields: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+
+    for pair in query_pairs(init_data) {
+        let (key, value) = pair?;
+
+        if key.is_empty()
+            // A newline would let a value smuggle in extra `key=value` lines.
+            || key.contains(&b'\n')
+            || value.contains(&b'\n')
+            || fields.iter().any(|(existing, _)| *existing == key)
+        {
+            return Err(InitDataError::Malformed);
+        }
+
+        if key == b"hash" || key == b"signature" {
+            continue;
+        }
+        fields.push((key, value));
+    }
+
+    fields.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut out = format!("{bot_id}{WEB_APP_DATA_SUFFIX}").into_bytes();
+    for (key, value) in fields {
+        out.push(b'\n');
+        out.extend_from_slice(&key);
+        out.push(b'=');
+        out.extend_from_slice(&value);
+    }
+
+    Ok(out)
+}
+
+/// Returns the Ed25519 `signature` field of raw initData (base64url, no padding).
+pub fn extract_signature(init_data: &[u8]) -> Result<[u8; SIG_LEN], InitDataError> {
+    for pair in query_pairs(init_data) {
+        let (key, value) = pair?;
+        if key != b"signature" {
+            continue;
+        }
+        let value = value
+            .strip_suffix(b"==")
+            .or_else(|| value.strip_suffix(b"="))
+            .unwrap_or(&value);
+        return base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(value)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(InitDataError::InvalidSignature);
+    }
+    Err(InitDataError::InvalidSignature)
+}
+
+/// Data of an Ed25519 precompile instruction checking one signature, laid out
+/// like `Ed25519Program.createInstructionWithPublicKey` in web3.js.
+pub fn ed25519_instruction_data(
+    pubkey: &[u8; PUBKEY_LEN],
+    signature: &[u8; SIG_LEN],
+    message: &[u8],
+) -> Vec<u8> {
+    let public_key_offset = ED25519_HEADER_LEN + ED25519_OFFSETS_LEN;
+    let signature_offset = public_key_offset + PUBKEY_LEN;
+    let message_data_offset = signature_offset + SIG_LEN;
+
+    let offsets = [
+        signature_offset as u16,
+        u16::MAX,
+        public_key_offset as u16,
+        u16::MAX,
+        message_data_offset as u16,
+        message.len() as u16,
+        u16::MAX,
+    ];
+
+    let mut data = Vec::with_capacity(message_data_offset + message.len());
+    data.extend_from_slice(&[1, 0]);
+    for offset in offsets {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(signature);
+    data.extend_from_slice(message);
+    data
+}
+
+/// Data of an Ed25519 precompile instruction checking one signature over
+/// `message_len` bytes at `message_offset` of instruction `message_instruction_index`,
+/// so the message is not sent twice.
+pub fn ed25519_instruction_data_referencing(
+    pubkey: &[u8; PUBKEY_LEN],
+    signature: &[u8; SIG_LEN],
+    message_instruction_index: u16,
+    message_offset: u16,
+    message_len: u16,
+) -> Vec<u8> {
+    let public_key_offset = ED25519_HEADER_LEN + ED25519_OFFSETS_LEN;
+    let signature_offset = public_key_offset + PUBKEY_LEN;
+
+    let offsets = [
+        signature_offset as u16,
+        u16::MAX,
+        public_key_offset as u16,
+        u16::MAX,
+        message_offset,
+        message_len,
+        message_instruction_index,
+    ];
+
+    let mut data = Vec::with_capacity(signature_offset + SIG_LEN);
+    data.extend_from_slice(&[1, 0]);
+    for offset in offsets {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+    data.extend_from_slice(pubkey);
+    data.extend_from_slice(signature);
+    data
+}
+
+/// URL-decoded `key=value` pairs of an initData query string.
+fn query_pairs(
+    init_data: &[u8],
+) -> impl Iterator<Item = Result<(Vec<u8>, Vec<u8>), InitDataError>> + '_ {
+    init_data.split(|b| *b == b'&').map(|pair| {
+        let eq = pair
+            .iter()
+            .position(|b| *b == b'=')
+            .ok_or(InitDataError::Malformed)?;
+        Ok((url_decode(&pair[..eq])?, url_decode(&pair[eq + 1..])?))
+    })
+}
+
+fn url_decode(input: &[u8]) -> Result<Vec<u8>, InitDataError> {
+    let hex_value = |b: u8| -> Result<u8, InitDataError> {
+        (b as char)
+            .to_digit(16)
+            .map(|v| v as u8)
+            .ok_or(InitDataError::Malformed)
+    };
+
+    let mut out = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'%' => {
+                let hex = input.get(i + 1..i + 3).ok_or(InitDataError::Malformed)?;
+                out.push((hex_value(hex[0])? << 4) | hex_value(hex[1])?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Validates a Telegram username and converts it to lowercase
+fn normalize_username(username: Option<&str>) -> Result<String, InitDataError> {
+    let username = username.ok_or(InitDataError::InvalidUsername)?;
+
+    if !(MIN_USERNAME_LEN..=MAX_USERNAME_LEN).contains(&username.len())
+        || !username
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+    {
+        return Err(InitDataError::InvalidUsername);
+    }
+
+    Ok(username.to_lowercase())
+}
+
+/// Returns the JSON payload of the `user=` field
+fn extract_user_payload(bytes: &[u8]) -> Result<&str, InitDataError> {
+    let s = core::str::from_utf8(bytes).map_err(|_| InitDataError::Malformed)?;
+
+    // Scope to the `user=` payload to avoid matching keys of other fields.
+    let user_start = if let Some(idx) = s.find("\nuser=") {
+        idx + "\nuser=".len()
+    } else if s.starts_with("user=") {
+        "user=".len()
+    } else {
+        return Err(InitDataError::Malformed);
+    };
+
+    let user_rest = &s[user_start..];
+    let user_end_rel = user_rest.find('\n').unwrap_or(user_rest.len());
+    Ok(&user_rest[..user_end_rel])
+}
+
+fn extract_auth_date(bytes: &[u8]) -> Result<u64, InitDataError> {
+    let s = core::str::from_utf8(bytes).map_err(|_| InitDataError::Malformed)?;
+
+    let start = s.find(AUTH_DATE_PREFIX).ok_or(InitDataError::Malformed)? + AUTH_DATE_PREFIX.len();
+
+    let rest = &s[start..];
+    let num_str = &rest[..rest.find('\n').unwrap_or(rest.len())];
+    if num_str.is_empty() || !num_str.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(InitDataError::Malformed);
+    }
+
+    match num_str.parse() {
+        Ok(0) => Err(InitDataError::InvalidAuthDate),
+        Ok(ts) => Ok(ts),
+        Err(_) => Err(InitDataError::Malformed),
+    }
+}
+
+#[cfg(not(target_os = "solana"))]
+pub use host::*;
+
+#[cfg(not(target_os = "solana"))]
+mod host {
+    use super::*;
+    use anchor_lang::solana_program::instruction::Instruction;
+    use anchor_lang::solana_program::pubkey::Pubkey;
+
+    pub const ED25519_PROGRAM_ID: Pubkey =
+        Pubkey::from_str_const("Ed25519SigVerify111111111111111111111111111");
+
+    /// Ed25519 precompile instruction to place before `verify_telegram_init_data`.
+    pub fn ed25519_instruction(
+        pubkey: &[u8; PUBKEY_LEN],
+        signature: &[u8; SIG_LEN],
+        message: &[u8],
+    ) -> Instruction {
+        Instruction {
+            program_id: ED25519_PROGRAM_ID,
+            accounts: vec![],
+            data: ed25519_instruction_data(pubkey, signature, message),
+        }
+    }
+
+    /// Ed25519 precompile instruction checking Telegram's signature over the
+    /// data-check-string of the `store_and_verify` instruction at `store_and_verify_index`.
+    pub fn store_and_verify_ed25519_instruction(
+        pubkey: &[u8; PUBKEY_LEN],
+        signature: &[u8; SIG_LEN],
+        store_and_verify_index: u16,
+        data_check_string_len: u16,
+    ) -> Instruction {
+        Instruction {
+            program_id: ED25519_PROGRAM_ID,
+            accounts: vec![],
+            data: ed25519_instruction_data_referencing(
+                pubkey,
+                signature,
+                store_and_verify_index,
+                STORE_AND_VERIFY_DATA_CHECK_STRING_OFFSET,
+                data_check_string_len,
+            ),
+        }
+    }
+
+    /// Checks raw initData like `store` and `verify_telegram_init_data` would,
+    /// including its `signature` under `telegram_pubkey`.
+    pub fn verify_init_data(
+        bot_id: u64,
+        init_data: &[u8],
+        telegram_pubkey: &[u8; PUBKEY_LEN],
+        now: u64,
+        max_auth_age_secs: u64,
+    ) -> Result<InitData, InitDataError> {
+        let parsed = parse_init_data(bot_id, init_data)?;
+        check_auth_date(parsed.auth_date, now, max_auth_age_secs)?;
+
+        let signature = extract_signature(init_data)?;
+        verify_signature(telegram_pubkey, &signature, &parsed.data_check_string)?;
+        Ok(parsed)
+    }
+
+    /// Strict Ed25519 verification, as done by the precompile.
+    pub fn verify_signature(
+        pubkey: &[u8; PUBKEY_LEN],
+        signature: &[u8; SIG_LEN],
+        message: &[u8],
+    ) -> Result<(), InitDataError> {
+        let pubkey = ed25519_dalek::PublicKey::from_bytes(pubkey)
+            .map_err(|_| InitDataError::InvalidSignature)?;
+        let signature = ed25519_dalek::Signature::from_bytes(signature)
+            .map_err(|_| InitDataError::InvalidSignature)?;
+        pubkey
+            .verify_strict(message, &signature)
+            .map_err(|_| InitDataError::InvalidSignature)
+    }
+}
@@ -1,79 +1,527 @@
 use anchor_lang::prelude::*;
+use anchor_lang::system_program::{transfer, Transfer};
 
-use anchor_lang::solana_program::sysvar::instructions::{
-    load_current_index_checked, load_instruction_at_checked,
-};
+use anchor_lang::solana_program::sysvar::instructions::load_instruction_at_checked;
 use core::str::FromStr;
 use hex_literal::hex;
+use std::borrow::Cow;
+
+pub mod event_log;
+pub mod init_data;
+pub mod telegram_user;
+
+use event_log::decode_event;
+use init_data::{
+    InitData, InitDataError, ED25519_HEADER_LEN, ED25519_OFFSETS_LEN, MAX_USERNAME_LEN,
+    MAX_VALIDATION_LEN, PUBKEY_LEN,
+};
+pub use telegram_user::TelegramUser;
 
 declare_id!("9yiphKYd4b69tR1ZPP8rNwtMeUwWgjYXaXdEzyNziNhz");
 
 // ---- Constants ----
-const MAX_VALIDATION_LEN: usize = 768;
-const MIN_USERNAME_LEN: usize = 5;
-const MAX_USERNAME_LEN: usize = 32;
-const USERNAME_PATTERN: &str = "\"username\":\"";
-const AUTH_DATE_PREFIX: &str = "\nauth_date=";
-
-const ED25519_HEADER_LEN: usize = 2; // [sig_count: u8, padding: u8]
-const ED25519_OFFSETS_LEN: usize = 14; // 7 * u16 (LE)
-const SIG_LEN: usize = 64;
-const PUBKEY_LEN: usize = 32;
 const TELEGRAM_PUBKEY_PROD: [u8; 32] =
     hex!("e7bf03a2fa4602af4580703d88dda5bb59f32ed8b02a56c187fe7d34caed242d");
+const MAX_TELEGRAM_KEYS: usize = 8;
+const MAX_BOT_IDS: usize = 8;
 
-const SESSION_SEED: &[u8] = b"tg_session";
+/// Seed of `TelegramSession` PDAs: `[SESSION_SEED, user_wallet]`.
+pub const SESSION_SEED: &[u8] = b"tg_session";
+const CONFIG_SEED: &[u8] = b"tg_config";
+const CONSUMED_SEED: &[u8] = b"tg_consumed";
+const BINDING_SEED: &[u8] = b"tg_username";
+const RELAYER_SEED: &[u8] = b"tg_relayer";
+/// Seed of `RelayerUsage` PDAs: `[RELAYER_USAGE_SEED, relayer, user_wallet]`.
+pub const RELAYER_USAGE_SEED: &[u8] = b"tg_relayer_usage";
+
+/// Prefix of the message a wallet signs to attest it owns a session: `prefix || payload_hash`.
+pub const WALLET_ATTESTATION_PREFIX: &[u8] = b"telegram-verification:wallet:";
 
 // ---- Program ----
 #[program]
 pub mod telegram_verification {
     use super::*;
 
-    pub fn store(ctx: Context<StoreTelegramInitData>, validation_bytes: Vec<u8>) -> Result<()> {
+    /// Creates the program config. Only the program upgrade authority can call this.
+    ///
+    /// `bot_ids` seeds the Mini App bots whose initData can be stored, so the config
+    /// is usable without a follow-up `add_bot_id`.
+    pub fn initialize_config(
+        ctx: Context<InitializeConfig>,
+        max_auth_age_secs: u64,
+        session_ttl_secs: u64,
+        bot_ids: Vec<u64>,
+    ) -> Result<()> {
+        require!(max_auth_age_secs > 0, ErrorCode::InvalidMaxAuthAge);
+        require!(session_ttl_secs > 0, ErrorCode::InvalidSessionTtl);
+        require!(!bot_ids.is_empty(), ErrorCode::InvalidBotId);
+
+        let config = &mut ctx.accounts.config;
+        config.authority = ctx.accounts.authority.key();
+        config.max_auth_age_secs = max_auth_age_secs;
+        config.session_ttl_secs = session_ttl_secs;
+        config.bump = ctx.bumps.config;
+        config.require_wallet_attestation = false;
+        config.auth_date_floor = 0;
+        config.telegram_keys = vec![TelegramKey {
+            pubkey: TELEGRAM_PUBKEY_PROD,
+            activated_at: 0,
+            expires_at: None,
+        }];
+        config.bot_ids = Vec::new();
+        for bot_id in bot_ids {
+            config.add_bot_id(bot_id)?;
+        }
+
+        Ok(())
+    }
+
+    /// Registers a Telegram signing key accepted from `activated_at` until `expires_at`.
+    pub fn add_telegram_key(
+        ctx: Context<UpdateConfig>,
+        pubkey: [u8; 32],
+        activated_at: u64,
+        expires_at: Option<u64>,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        ctx.accounts
+            .config
+            .add_telegram_key(pubkey, activated_at, expires_at, now)
+    }
+
+    /// Stops accepting a Telegram signing key immediately.
+    pub fn retire_telegram_key(ctx: Context<UpdateConfig>, pubkey: [u8; 32]) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        let key = ctx.accounts.config.find_telegram_key_mut(&pubkey)?;
+        key.expires_at = Some(now);
+        Ok(())
+    }
+
+    /// Activates `new_pubkey` now and keeps accepting `old_pubkey` for `overlap_secs`,
+    /// so initData signed just before the rotation can still be verified.
+    ///
+    /// `old_pubkey` must still be active; a retired or expired key is not revived.
+    pub fn rotate_telegram_key(
+        ctx: Context<UpdateConfig>,
+        old_pubkey: [u8; 32],
+        new_pubkey: [u8; 32],
+        overlap_secs: u64,
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        ctx.accounts
+            .config
+            .rotate_telegram_key(old_pubkey, new_pubkey, overlap_secs, now)
+    }
+
+    /// Allows initData issued for the Mini App bot `bot_id`.
+    pub fn add_bot_id(ctx: Context<UpdateConfig>, bot_id: u64) -> Result<()> {
+        ctx.accounts.config.add_bot_id(bot_id)
+    }
+
+    /// Stops accepting initData issued for the Mini App bot `bot_id`.
+    pub fn remove_bot_id(ctx: Context<UpdateConfig>, bot_id: u64) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        require!(config.bot_ids.contains(&bot_id), ErrorCode::BotIdNotAllowed);
+        config.bot_ids.retain(|allowed| *allowed != bot_id);
+        Ok(())
+    }
+
+    /// Updates the maximum accepted age of Telegram `auth_date`.
+    pub fn set_max_auth_age(ctx: Context<UpdateConfig>, max_auth_age_secs: u64) -> Result<()> {
+        require!(max_auth_age_secs > 0, ErrorCode::InvalidMaxAuthAge);
+        let now = Clock::get()?.unix_timestamp as u64;
+        ctx.accounts.config.set_max_auth_age(max_auth_age_secs, now);
+        Ok(())
+    }
+
+    /// Updates how long a verification stays valid. Applies to sessions verified afterwards.
+    pub fn set_session_ttl(ctx: Context<UpdateConfig>, session_ttl_secs: u64) -> Result<()> {
+        require!(session_ttl_secs > 0, ErrorCode::InvalidSessionTtl);
+        ctx.accounts.config.session_ttl_secs = session_ttl_secs;
+        Ok(())
+    }
+
+    /// Requires `verify_telegram_init_data` to carry a signature by the session wallet
+    /// over `wallet_attestation_message(payload_hash)`.
+    ///
+    /// This is a deployment-wide policy set by the config authority, not something a
+    /// caller can ask for per verification. `store_and_verify` never needs it, since
+    /// the wallet signs that transaction itself.
+    pub fn set_require_wallet_attestation(
+        ctx: Context<UpdateConfig>,
+        require_wallet_attestation: bool,
+    ) -> Result<()> {
+        ctx.accounts.config.require_wallet_attestation = require_wallet_attestation;
+        Ok(())
+    }
+
+    /// Registers or updates a relayer allowed to pay for `relayed_store`.
+    pub fn set_relayer(
+        ctx: Context<SetRelayer>,
+        relayer: Pubkey,
+        store_fee_lamports: u64,
+        max_stores_per_wallet: u32,
+    ) -> Result<()> {
         require!(
-            validation_bytes.len() <= MAX_VALIDATION_LEN,
-            ErrorCode::InvalidValidationBytesLength
+            max_stores_per_wallet > 0,
+            ErrorCode::InvalidRelayerStoreLimit
         );
 
-        let session = &mut ctx.accounts.session;
+        let relayer_config = &mut ctx.accounts.relayer_config;
+        relayer_config.relayer = relayer;
+        relayer_config.store_fee_lamports = store_fee_lamports;
+        relayer_config.max_stores_per_wallet = max_stores_per_wallet;
+        relayer_config.bump = ctx.bumps.relayer_config;
+        Ok(())
+    }
+
+    /// Deregisters a relayer. Fees already owed to it stay reimbursable.
+    pub fn remove_relayer(_ctx: Context<RemoveRelayer>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Stores raw Mini App initData (`Telegram.WebApp.initData`) issued for `bot_id`.
+    ///
+    /// The data-check-string signed by Telegram is rebuilt on-chain and kept as
+    /// `validation_bytes` for `verify_telegram_init_data`.
+    pub fn store(
+        ctx: Context<StoreTelegramInitData>,
+        bot_id: u64,
+        init_data: Vec<u8>,
+    ) -> Result<()> {
+        store_init_data(
+            &mut ctx.accounts.session,
+            ctx.accounts.previous_binding.as_mut(),
+            ctx.accounts.user.key(),
+            ctx.accounts.payer.key(),
+            &ctx.accounts.config,
+            bot_id,
+            init_data::parse_init_data(bot_id, &init_data)?,
+        )
+    }
 
-        let username = extract_username(&validation_bytes)?;
+    /// `store` paid for by a registered relayer.
+    ///
+    /// Each call counts against the relayer's per-wallet store limit and adds its
+    /// store fee to what the user owes the relayer. The session records the relayer,
+    /// and claims through `telegram_transfer` settle the fee, see `settle_relayer_fee`.
+    pub fn relayed_store(
+        ctx: Context<RelayedStoreTelegramInitData>,
+        bot_id: u64,
+        init_data: Vec<u8>,
+    ) -> Result<()> {
+        let relayer_config = &ctx.accounts.relayer_config;
+        let usage = &mut ctx.accounts.usage;
         require!(
-            (MIN_USERNAME_LEN..=MAX_USERNAME_LEN).contains(&username.len()),
-            ErrorCode::InvalidTelegramUsername
+            usage.stores < relayer_config.max_stores_per_wallet,
+            ErrorCode::RelayerStoreLimitReached
         );
 
-        let auth_at = extract_auth_date(&validation_bytes)?;
-        require!(auth_at > 0, ErrorCode::InvalidTelegramAuthDate);
+        usage.relayer = relayer_config.relayer;
+        usage.user_wallet = ctx.accounts.user.key();
+        usage.stores += 1;
+        usage.owed_lamports = usage
+            .owed_lamports
+            .checked_add(relayer_config.store_fee_lamports)
+            .ok_or(ErrorCode::Overflow)?;
+        usage.bump = ctx.bumps.usage;
+
+        store_init_data(
+            &mut ctx.accounts.session,
+            ctx.accounts.previous_binding.as_mut(),
+            ctx.accounts.user.key(),
+            ctx.accounts.relayer.key(),
+            &ctx.accounts.config,
+            bot_id,
+            init_data::parse_init_data(bot_id, &init_data)?,
+        )?;
+        ctx.accounts.session.relayer = Some(relayer_config.relayer);
+        Ok(())
+    }
+
+    /// Pays back the fees a user owes a relayer.
+    ///
+    /// Meant to be bundled right after the user's first claim, which is usually
+    /// the first time a gasless wallet holds any SOL.
+    pub fn reimburse_relayer(ctx: Context<ReimburseRelayer>) -> Result<()> {
+        let usage = &mut ctx.accounts.usage;
+        let amount = usage.owed_lamports;
+        require!(amount > 0, ErrorCode::NothingToReimburse);
 
-        session.user_wallet = ctx.accounts.user.key();
-        session.username = username;
-        session.auth_at = auth_at;
-        session.validation_bytes = validation_bytes;
-        session.verified = false;
-        session.verified_at = None;
+        transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.user.to_account_info(),
+                    to: ctx.accounts.relayer.to_account_info(),
+                },
+            ),
+            amount,
+        )?;
 
+        usage.owed_lamports = 0;
+        usage.reimbursed_lamports = usage
+            .reimbursed_lamports
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
+        Ok(())
+    }
+
+    /// Forwards lamports paid into a usage account on to its relayer, up to what is owed.
+    ///
+    /// Permissionless: `telegram_transfer` claims pay the owed fees into `usage` and
+    /// settle them here in the same instruction.
+    pub fn settle_relayer_fee(ctx: Context<SettleRelayerFee>) -> Result<()> {
+        let usage_ai = ctx.accounts.usage.to_account_info();
+        let rent_min = Rent::get()?.minimum_balance(usage_ai.data_len());
+        let amount = usage_ai
+            .lamports()
+            .saturating_sub(rent_min)
+            .min(ctx.accounts.usage.owed_lamports);
+        require!(amount > 0, ErrorCode::NothingToReimburse);
+
+        **usage_ai.try_borrow_mut_lamports()? -= amount;
+        **ctx.accounts.relayer.try_borrow_mut_lamports()? += amount;
+
+        let usage = &mut ctx.accounts.usage;
+        usage.owed_lamports -= amount;
+        usage.reimbursed_lamports = usage
+            .reimbursed_lamports
+            .checked_add(amount)
+            .ok_or(ErrorCode::Overflow)?;
         Ok(())
     }
 
     pub fn verify_telegram_init_data(ctx: Context<VerifyTelegramInitData>) -> Result<()> {
-        let expected = &ctx.accounts.session.validation_bytes;
+        let config = &ctx.accounts.config;
+        verify_session_signatures(
+            &ctx.accounts.session,
+            config,
+            &ctx.accounts.instructions,
+            config.require_wallet_attestation,
+        )?;
+
+        verify_session(
+            &mut ctx.accounts.session,
+            &ctx.accounts.config,
+            ctx.accounts.payer.key(),
+            &mut ctx.accounts.consumed,
+            &mut ctx.accounts.binding,
+            ctx.bumps.binding,
+            ctx.accounts.previous_session.as_mut(),
+        )
+    }
+
+    /// `store` and `verify_telegram_init_data` in a single instruction.
+    ///
+    /// Takes the data-check-string Telegram signed rather than raw initData, so
+    /// the Ed25519 instruction can point its message at this instruction's data
+    /// instead of carrying a second copy, see `store_and_verify_ed25519_instruction`.
+    /// Even so, only data-check-strings up to `MAX_STORE_AND_VERIFY_LEN` are sure
+    /// to fit in one transaction.
+    ///
+    /// `payload_hash` (sha256 of the data-check-string) and `username` seed the
+    /// consumed-payload and username-binding PDAs and must match it.
+    pub fn store_and_verify(
+        ctx: Context<StoreAndVerifyTelegramInitData>,
+        bot_id: u64,
+        data_check_string: Vec<u8>,
+        payload_hash: [u8; 32],
+        username: String,
+    ) -> Result<()> {
+        // Re-storing under the same username releases the binding `verify_session` takes back.
+        let previous_binding = match ctx.accounts.previous_binding.as_mut() {
+            Some(previous) if previous.key() == ctx.accounts.binding.key() => {
+                Some(&mut ctx.accounts.binding)
+            }
+            previous => previous,
+        };
+        store_init_data(
+            &mut ctx.accounts.session,
+            previous_binding,
+            ctx.accounts.user.key(),
+            ctx.accounts.payer.key(),
+            &ctx.accounts.config,
+            bot_id,
+            init_data::parse_data_check_string(bot_id, &data_check_string)?,
+        )?;
+        require!(
+            ctx.accounts.session.payload_hash == payload_hash,
+            ErrorCode::InvalidTelegramMessage
+        );
+        require!(
+            ctx.accounts.session.username == username,
+            ErrorCode::InvalidTelegramUsername
+        );
+
+        // `user` signs this transaction, so no separate wallet attestation is needed.
+        verify_session_signatures(
+            &ctx.accounts.session,
+            &ctx.accounts.config,
+            &ctx.accounts.instructions,
+            false,
+        )?;
+
+        verify_session(
+            &mut ctx.accounts.session,
+            &ctx.accounts.config,
+            ctx.accounts.payer.key(),
+            &mut ctx.accounts.consumed,
+            &mut ctx.accounts.binding,
+            ctx.bumps.binding,
+            ctx.accounts.previous_session.as_mut(),
+        )
+    }
+
+    /// Drops the verification of the signer's session and releases its username binding.
+    pub fn revoke_session(ctx: Context<RevokeSession>) -> Result<()> {
+        let session_key = ctx.accounts.session.key();
+        release_binding(&ctx.accounts.session, ctx.accounts.binding.as_mut())?;
 
-        verify_previous_ed25519_ix(&ctx.accounts.instructions, expected)?;
         let session = &mut ctx.accounts.session;
-        session.verified = true;
-        session.verified_at = Some(Clock::get()?.unix_timestamp as u64);
+        let verified_at = session.verified_at;
+        session.clear_verification();
+
+        emit!(SessionRevoked {
+            session: session_key,
+            user_wallet: session.user_wallet,
+            username: session.username.clone(),
+            auth_at: session.auth_at,
+            verified_at,
+            revoked_at: Clock::get()?.unix_timestamp as u64,
+            closed: false,
+        });
+        Ok(())
+    }
+
+    /// Closes the signer's session and returns its rent to whoever paid for it.
+    ///
+    /// A session stored by a relayer can only be closed once its fees are paid,
+    /// otherwise closing and storing again would drop them.
+    pub fn close_session(ctx: Context<CloseSession>) -> Result<()> {
+        if let Some(relayer) = ctx.accounts.session.relayer {
+            let usage = ctx
+                .accounts
+                .relayer_usage
+                .as_ref()
+                .ok_or_else(|| error!(ErrorCode::InvalidRelayer))?;
+            require_keys_eq!(usage.relayer, relayer, ErrorCode::InvalidRelayer);
+            require!(usage.owed_lamports == 0, ErrorCode::RelayerFeeOutstanding);
+        }
+
+        let session_key = ctx.accounts.session.key();
+        release_binding(&ctx.accounts.session, ctx.accounts.binding.as_mut())?;
+
+        let session = &ctx.accounts.session;
+        emit!(SessionRevoked {
+            session: session_key,
+            user_wallet: session.user_wallet,
+            username: session.username.clone(),
+            auth_at: session.auth_at,
+            verified_at: session.verified_at,
+            revoked_at: Clock::get()?.unix_timestamp as u64,
+            closed: true,
+        });
+        Ok(())
+    }
+
+    /// Grows a session stored before expiry and replay protection to the current layout.
+    ///
+    /// Permissionless. The migrated session keeps its stored initData but is not
+    /// verified, so its owner has to verify again.
+    pub fn migrate_session(ctx: Context<MigrateSession>) -> Result<()> {
+        let session = &ctx.accounts.session;
+        let space = 8 + TelegramSession::INIT_SPACE;
+        let migrated = TelegramSession::try_from_legacy(&session.try_borrow_data()?)?;
+
+        let shortfall = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(session.lamports());
+        if shortfall > 0 {
+            transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.payer.to_account_info(),
+                        to: session.to_account_info(),
+                    },
+                ),
+                shortfall,
+            )?;
+        }
+        session.resize(space)?;
+
+        let mut data = session.try_borrow_mut_data()?;
+        data.fill(0);
+        migrated.try_serialize(&mut &mut data[..])?;
+        Ok(())
+    }
+
+    /// Closes a consumed-payload record once its initData is too old to be stored
+    /// again, returning the rent to whoever paid for the verification.
+    pub fn close_consumed_init_data(
+        ctx: Context<CloseConsumedInitData>,
+        _payload_hash: [u8; 32],
+    ) -> Result<()> {
+        let now = Clock::get()?.unix_timestamp as u64;
+        require!(
+            ctx.accounts.consumed.is_closable(&ctx.accounts.config, now),
+            ErrorCode::ConsumedInitDataStillFresh
+        );
         Ok(())
     }
 }
 
 // ---- Accounts ----
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VerificationConfig::INIT_SPACE,
+        seeds = [CONFIG_SEED],
+        bump
+    )]
+    pub config: Account<'info, VerificationConfig>,
+
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program: Program<'info, crate::program::TelegramVerification>,
+
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(authority.key()) @ ErrorCode::Unauthorized
+    )]
+    pub program_data: Account<'info, ProgramData>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, VerificationConfig>,
+}
+
 #[derive(Accounts)]
 pub struct StoreTelegramInitData<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
     pub user: Signer<'info>,
 
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, VerificationConfig>,
+
     #[account(
         init_if_needed,
         payer = payer,
@@ -83,21 +531,470 @@ pub struct StoreTelegramInitData<'info> {
     )]
     pub session: Account<'info, TelegramSession>,
 
+    /// Binding of the username the session was verified for, required if it is verified.
+    #[account(
+        mut,
+        seeds = [BINDING_SEED, session.username.as_bytes()],
+        bump = previous_binding.bump
+    )]
+    pub previous_binding: Option<Account<'info, UsernameBinding>>,
+
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+#[instruction(relayer: Pubkey)]
+pub struct SetRelayer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, VerificationConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + RelayerConfig::INIT_SPACE,
+        seeds = [RELAYER_SEED, relayer.as_ref()],
+        bump
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveRelayer<'info> {
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [CONFIG_SEED],
+        bump = config.bump,
+        has_one = authority @ ErrorCode::Unauthorized
+    )]
+    pub config: Account<'info, VerificationConfig>,
+
+    #[account(
+        mut,
+        close = authority,
+        seeds = [RELAYER_SEED, relayer_config.relayer.as_ref()],
+        bump = relayer_config.bump
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+}
+
+#[derive(Accounts)]
+pub struct RelayedStoreTelegramInitData<'info> {
+    #[account(mut)]
+    pub relayer: Signer<'info>,
+    pub user: Signer<'info>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, VerificationConfig>,
+
+    #[account(
+        seeds = [RELAYER_SEED, relayer.key().as_ref()],
+        bump = relayer_config.bump
+    )]
+    pub relayer_config: Account<'info, RelayerConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + RelayerUsage::INIT_SPACE,
+        seeds = [RELAYER_USAGE_SEED, relayer.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub usage: Account<'info, RelayerUsage>,
+
+    #[account(
+        init_if_needed,
+        payer = relayer,
+        space = 8 + TelegramSession::INIT_SPACE,
+        seeds = [SESSION_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    /// Binding of the username the session was verified for, required if it is verified.
+    #[account(
+        mut,
+        seeds = [BINDING_SEED, session.username.as_bytes()],
+        bump = previous_binding.bump
+    )]
+    pub previous_binding: Option<Account<'info, UsernameBinding>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReimburseRelayer<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    /// CHECK: only receives lamports; must be the relayer recorded in `usage`.
+    #[account(mut, address = usage.relayer @ ErrorCode::InvalidRelayer)]
+    pub relayer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [RELAYER_USAGE_SEED, usage.relayer.as_ref(), user.key().as_ref()],
+        bump = usage.bump
+    )]
+    pub usage: Account<'info, RelayerUsage>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleRelayerFee<'info> {
+    /// CHECK: only receives lamports; must be the relayer recorded in `usage`.
+    #[account(mut, address = usage.relayer @ ErrorCode::InvalidRelayer)]
+    pub relayer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        seeds = [RELAYER_USAGE_SEED, usage.relayer.as_ref(), usage.user_wallet.as_ref()],
+        bump = usage.bump
+    )]
+    pub usage: Account<'info, RelayerUsage>,
+}
+
 #[derive(Accounts)]
 pub struct VerifyTelegramInitData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
     #[account(mut)]
     pub session: Account<'info, TelegramSession>,
 
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, VerificationConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ConsumedInitData::INIT_SPACE,
+        seeds = [CONSUMED_SEED, session.payload_hash.as_ref()],
+        bump
+    )]
+    pub consumed: Account<'info, ConsumedInitData>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UsernameBinding::INIT_SPACE,
+        seeds = [BINDING_SEED, session.username.as_bytes()],
+        bump
+    )]
+    pub binding: Account<'info, UsernameBinding>,
+
+    /// Session the username is currently bound to, required when the binding moves.
+    #[account(mut)]
+    pub previous_session: Option<Account<'info, TelegramSession>>,
+
     /// CHECK: we only read from instructions sysvar
     #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
     pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bot_id: u64, data_check_string: Vec<u8>, payload_hash: [u8; 32], username: String)]
+pub struct StoreAndVerifyTelegramInitData<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub user: Signer<'info>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, VerificationConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + TelegramSession::INIT_SPACE,
+        seeds = [SESSION_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    /// Binding of the username the session was verified for, required if it is verified.
+    #[account(
+        mut,
+        seeds = [BINDING_SEED, session.username.as_bytes()],
+        bump = previous_binding.bump
+    )]
+    pub previous_binding: Option<Account<'info, UsernameBinding>>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + ConsumedInitData::INIT_SPACE,
+        seeds = [CONSUMED_SEED, payload_hash.as_ref()],
+        bump
+    )]
+    pub consumed: Account<'info, ConsumedInitData>,
+
+    #[account(
+        init_if_needed,
+        payer = payer,
+        space = 8 + UsernameBinding::INIT_SPACE,
+        seeds = [BINDING_SEED, username.as_bytes()],
+        bump
+    )]
+    pub binding: Account<'info, UsernameBinding>,
+
+    /// Session the username is currently bound to, required when the binding moves.
+    #[account(mut)]
+    pub previous_session: Option<Account<'info, TelegramSession>>,
+
+    /// CHECK: we only read from instructions sysvar
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: AccountInfo<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeSession<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SESSION_SEED, user.key().as_ref()],
+        bump,
+        constraint = session.user_wallet == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    #[account(
+        mut,
+        seeds = [BINDING_SEED, session.username.as_bytes()],
+        bump = binding.bump
+    )]
+    pub binding: Option<Account<'info, UsernameBinding>>,
+}
+
+#[derive(Accounts)]
+pub struct CloseSession<'info> {
+    pub user: Signer<'info>,
+
+    /// CHECK: only receives the rent; must be whoever paid it.
+    #[account(mut, address = session.rent_payer @ ErrorCode::InvalidRentPayer)]
+    pub rent_payer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        close = rent_payer,
+        seeds = [SESSION_SEED, user.key().as_ref()],
+        bump,
+        constraint = session.user_wallet == user.key() @ ErrorCode::Unauthorized
+    )]
+    pub session: Account<'info, TelegramSession>,
+
+    #[account(
+        mut,
+        seeds = [BINDING_SEED, session.username.as_bytes()],
+        bump = binding.bump
+    )]
+    pub binding: Option<Account<'info, UsernameBinding>>,
+
+    /// Fees owed to the relayer that stored the session, required for sessions stored by one.
+    #[account(
+        seeds = [RELAYER_USAGE_SEED, relayer_usage.relayer.as_ref(), user.key().as_ref()],
+        bump = relayer_usage.bump
+    )]
+    pub relayer_usage: Option<Account<'info, RelayerUsage>>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateSession<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Only used as a seed
+    pub user: UncheckedAccount<'info>,
+
+    /// CHECK: Legacy session, too short to deserialize as `TelegramSession`
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [SESSION_SEED, user.key().as_ref()],
+        bump
+    )]
+    pub session: UncheckedAccount<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(payload_hash: [u8; 32])]
+pub struct CloseConsumedInitData<'info> {
+    /// CHECK: only receives the rent; must be the recorded payer.
+    #[account(mut, address = consumed.payer @ ErrorCode::Unauthorized)]
+    pub payer: UncheckedAccount<'info>,
+
+    #[account(seeds = [CONFIG_SEED], bump = config.bump)]
+    pub config: Account<'info, VerificationConfig>,
+
+    #[account(
+        mut,
+        close = payer,
+        seeds = [CONSUMED_SEED, payload_hash.as_ref()],
+        bump
+    )]
+    pub consumed: Account<'info, ConsumedInitData>,
 }
 
 // ---- State ----
 
+#[account]
+#[derive(InitSpace)]
+pub struct VerificationConfig {
+    pub authority: Pubkey,
+    /// Maximum age of Telegram `auth_date` accepted by `store` and `verify_telegram_init_data`.
+    pub max_auth_age_secs: u64,
+    /// How long a verification stays valid, used to derive `TelegramSession::verified_until`.
+    pub session_ttl_secs: u64,
+    pub bump: u8,
+
+    /// Telegram signing keys accepted by `verify_telegram_init_data`.
+    #[max_len(MAX_TELEGRAM_KEYS)]
+    pub telegram_keys: Vec<TelegramKey>,
+
+    /// Mini App bots whose initData can be stored.
+    #[max_len(MAX_BOT_IDS)]
+    pub bot_ids: Vec<u64>,
+
+    /// Whether `verify_telegram_init_data` needs a wallet-signed attestation, see
+    /// `set_require_wallet_attestation`.
+    pub require_wallet_attestation: bool,
+
+    /// initData with an older `auth_date` is expired whatever `max_auth_age_secs` says.
+    pub auth_date_floor: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, InitSpace)]
+pub struct TelegramKey {
+    pub pubkey: [u8; 32],
+    pub activated_at: u64,
+    pub expires_at: Option<u64>,
+}
+
+impl TelegramKey {
+    pub fn is_active(&self, now: u64) -> bool {
+        self.activated_at <= now && self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+impl VerificationConfig {
+    /// Whether initData with this `auth_date` can be stored or verified at `now`.
+    pub fn accepts_auth_date(&self, auth_at: u64, now: u64) -> bool {
+        auth_at >= self.auth_date_floor
+            && init_data::check_auth_date(auth_at, now, self.max_auth_age_secs).is_ok()
+    }
+
+    /// Updates the maximum initData age.
+    ///
+    /// Raising it keeps initData that already expired under the old limit expired,
+    /// since its `ConsumedInitData` record may have been closed since.
+    pub fn set_max_auth_age(&mut self, max_auth_age_secs: u64, now: u64) {
+        if max_auth_age_secs > self.max_auth_age_secs {
+            let floor = now.saturating_sub(self.max_auth_age_secs);
+            self.auth_date_floor = self.auth_date_floor.max(floor);
+        }
+        self.max_auth_age_secs = max_auth_age_secs;
+    }
+
+    /// Allows initData issued for the Mini App bot `bot_id`, see `add_bot_id`.
+    pub fn add_bot_id(&mut self, bot_id: u64) -> Result<()> {
+        require!(bot_id > 0, ErrorCode::InvalidBotId);
+        require!(
+            !self.bot_ids.contains(&bot_id),
+            ErrorCode::BotIdAlreadyAllowed
+        );
+        require!(
+            self.bot_ids.len() < MAX_BOT_IDS,
+            ErrorCode::BotIdAllowlistFull
+        );
+        self.bot_ids.push(bot_id);
+        Ok(())
+    }
+
+    pub fn is_accepted_telegram_key(&self, pubkey: &[u8], now: u64) -> bool {
+        self.telegram_keys
+            .iter()
+            .any(|key| key.pubkey.as_slice() == pubkey && key.is_active(now))
+    }
+
+    fn find_telegram_key_mut(&mut self, pubkey: &[u8; 32]) -> Result<&mut TelegramKey> {
+        self.telegram_keys
+            .iter_mut()
+            .find(|key| &key.pubkey == pubkey)
+            .ok_or_else(|| error!(ErrorCode::TelegramKeyNotFound))
+    }
+
+    /// Replaces the active key `old_pubkey` with `new_pubkey`, see `rotate_telegram_key`.
+    ///
+    /// The overlap never extends the old key past an expiry it already had.
+    pub fn rotate_telegram_key(
+        &mut self,
+        old_pubkey: [u8; 32],
+        new_pubkey: [u8; 32],
+        overlap_secs: u64,
+        now: u64,
+    ) -> Result<()> {
+        let old_key = self.find_telegram_key_mut(&old_pubkey)?;
+        require!(old_key.is_active(now), ErrorCode::TelegramKeyNotActive);
+        let overlap_end = now.saturating_add(overlap_secs);
+        old_key.expires_at = Some(
+            old_key
+                .expires_at
+                .map_or(overlap_end, |expires_at| expires_at.min(overlap_end)),
+        );
+
+        self.add_telegram_key(new_pubkey, now, None, now)
+    }
+
+    fn add_telegram_key(
+        &mut self,
+        pubkey: [u8; 32],
+        activated_at: u64,
+        expires_at: Option<u64>,
+        now: u64,
+    ) -> Result<()> {
+        require!(
+            expires_at.is_none_or(|expires_at| activated_at < expires_at),
+            ErrorCode::InvalidTelegramKeyWindow
+        );
+        require!(
+            !self.telegram_keys.iter().any(|key| key.pubkey == pubkey),
+            ErrorCode::TelegramKeyAlreadyRegistered
+        );
+
+        // Make room by dropping keys that can never be accepted again.
+        self.telegram_keys
+            .retain(|key| key.expires_at.is_none_or(|expires_at| now < expires_at));
+        require!(
+            self.telegram_keys.len() < MAX_TELEGRAM_KEYS,
+            ErrorCode::TelegramKeyRegistryFull
+        );
+
+        self.telegram_keys.push(TelegramKey {
+            pubkey,
+            activated_at,
+            expires_at,
+        });
+        Ok(())
+    }
+}
+
 #[account]
 #[derive(InitSpace)]
 pub struct TelegramSession {
@@ -112,166 +1009,513 @@ pub struct TelegramSession {
     pub verified: bool,
     pub auth_at: u64,
     pub verified_at: Option<u64>,
+
+    /// sha256 of `validation_bytes`, used to seed the `ConsumedInitData` PDA.
+    pub payload_hash: [u8; 32],
+
+    /// Immutable numeric Telegram user id from the `user=` payload.
+    pub telegram_id: u64,
+
+    pub verified_until: Option<u64>,
+
+    /// Bot the initData was issued for, the `<bot_id>:WebAppData` prefix of `validation_bytes`.
+    pub bot_id: u64,
+
+    /// Paid the session's rent and gets it back on close.
+    pub rent_payer: Pubkey,
+
+    /// Relayer of the last `relayed_store`, owed its fees out of the wallet's claims.
+    pub relayer: Option<Pubkey>,
 }
 
-// ---- Helpers ----
-fn verify_previous_ed25519_ix(instructions_ai: &AccountInfo, expected_msg: &[u8]) -> Result<()> {
-    // 1) Load previous ix
-    let cur = load_current_index_checked(instructions_ai)
-        .map_err(|_| error!(ErrorCode::NotVerified))? as usize;
-    require!(cur > 0, ErrorCode::NotVerified);
-    let ix = load_instruction_at_checked(cur - 1, instructions_ai)
-        .map_err(|_| error!(ErrorCode::NotVerified))?;
-
-    // 2) Ensure Ed25519 program id
-    let ed25519_id = Pubkey::from_str("Ed25519SigVerify111111111111111111111111111")
-        .map_err(|_| error!(ErrorCode::InvalidEd25519))?;
-    require_keys_eq!(ix.program_id, ed25519_id, ErrorCode::InvalidEd25519);
-
-    // 3) basic bounds check
-    // Ed25519 ix data = [header(2)] [offsets(14)] [payload...]
-    let data = ix.data.as_slice();
-    require!(data.len() >= ED25519_HEADER_LEN, ErrorCode::InvalidEd25519);
-
-    // Exactly one signature
-    let sig_count = data[0] as usize;
-    require!(sig_count == 1, ErrorCode::InvalidEd25519);
-
-    // Read 7 * u16 (LE) offsets for the single signature
-    let need = ED25519_OFFSETS_LEN;
-    let offs = data
-        .get(ED25519_HEADER_LEN..ED25519_HEADER_LEN + need)
-        .ok_or_else(|| error!(ErrorCode::InvalidEd25519))?;
-
-    let read_u16 = |i: usize| -> u16 { (offs[i] as u16) | ((offs[i + 1] as u16) << 8) };
-
-    let signature_offset = read_u16(0) as usize;
-    let signature_instruction_index = read_u16(2);
-    let public_key_offset = read_u16(4) as usize;
-    let public_key_instruction_index = read_u16(6);
-    let message_data_offset = read_u16(8) as usize;
-    let message_data_size = read_u16(10) as usize;
-    let message_instruction_index = read_u16(12);
-
-    // All instruction indices must be 0xFFFF (relative to this instruction)
-    require!(
-        signature_instruction_index == u16::MAX
-            && public_key_instruction_index == u16::MAX
-            && message_instruction_index == u16::MAX,
-        ErrorCode::InvalidEd25519
-    );
+/// `TelegramSession` as stored before expiry and replay protection.
+#[derive(AnchorDeserialize)]
+struct LegacyTelegramSession {
+    user_wallet: Pubkey,
+    username: String,
+    validation_bytes: Vec<u8>,
+    _verified: bool,
+    auth_at: u64,
+    _verified_at: Option<u64>,
+}
+
+impl TelegramSession {
+    /// Reads a session account in the legacy layout, discriminator included.
+    ///
+    /// The result is unverified; fields the legacy layout lacks are zeroed. An
+    /// account already sized for the current layout is rejected.
+    pub fn try_from_legacy(data: &[u8]) -> Result<Self> {
+        require!(
+            data.len() < 8 + TelegramSession::INIT_SPACE,
+            ErrorCode::SessionAlreadyMigrated
+        );
+        let mut rest = data
+            .strip_prefix(TelegramSession::DISCRIMINATOR)
+            .ok_or(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch)?;
+        let legacy = LegacyTelegramSession::deserialize(&mut rest)
+            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
 
-    // Compute absolute slices and bounds-check
-    let sig_abs = signature_offset;
-    let pk_abs = public_key_offset;
-    let msg_abs = message_data_offset;
+        Ok(Self {
+            user_wallet: legacy.user_wallet,
+            username: legacy.username,
+            validation_bytes: legacy.validation_bytes,
+            verified: false,
+            auth_at: legacy.auth_at,
+            verified_at: None,
+            payload_hash: [0; 32],
+            telegram_id: 0,
+            verified_until: None,
+            bot_id: 0,
+            rent_payer: legacy.user_wallet,
+            relayer: None,
+        })
+    }
 
-    require!(
-        sig_abs >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN,
-        ErrorCode::InvalidEd25519
-    );
-    require!(
-        pk_abs >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN,
-        ErrorCode::InvalidEd25519
-    );
-    require!(
-        msg_abs >= ED25519_HEADER_LEN + ED25519_OFFSETS_LEN,
-        ErrorCode::InvalidEd25519
-    );
+    /// Whether the session holds a verification that has not expired at `now`.
+    pub fn is_verified(&self, now: u64) -> bool {
+        self.verified && self.verified_until.is_some_and(|until| now < until)
+    }
 
-    require!(sig_abs + SIG_LEN <= data.len(), ErrorCode::InvalidEd25519);
-    require!(pk_abs + PUBKEY_LEN <= data.len(), ErrorCode::InvalidEd25519);
-    require!(
-        msg_abs + message_data_size <= data.len(),
-        ErrorCode::InvalidEd25519
-    );
+    fn clear_verification(&mut self) {
+        self.verified = false;
+        self.verified_at = None;
+        self.verified_until = None;
+    }
+}
+
+/// Canonical owner of a Telegram username, seeded by the lowercase username.
+#[account]
+#[derive(InitSpace)]
+pub struct UsernameBinding {
+    #[max_len(MAX_USERNAME_LEN)]
+    pub username: String,
+    pub user_wallet: Pubkey,
+    pub telegram_id: u64,
+    pub session: Pubkey,
+    pub auth_at: u64,
+    pub bound_at: u64,
+    pub bump: u8,
+}
+
+impl UsernameBinding {
+    /// Unbinds the username if it is still held by `session`.
+    fn release(&mut self, session: Pubkey) {
+        if self.session == session {
+            self.user_wallet = Pubkey::default();
+            self.session = Pubkey::default();
+        }
+    }
+}
+
+/// Marks a signed initData payload as used, seeded by its sha256.
+#[account]
+#[derive(InitSpace)]
+pub struct ConsumedInitData {
+    pub user_wallet: Pubkey,
+    pub consumed_at: u64,
+    /// `auth_date` of the payload; the record can be closed once it is no longer fresh.
+    pub auth_at: u64,
+    /// Paid the rent and gets it back on close.
+    pub payer: Pubkey,
+    /// When the payload expires under the `max_auth_age_secs` in force when it was consumed.
+    pub expires_at: u64,
+}
+
+impl ConsumedInitData {
+    /// Whether the record can be closed at `now` without its payload becoming
+    /// storable again.
+    pub fn is_closable(&self, config: &VerificationConfig, now: u64) -> bool {
+        now > self.expires_at && !config.accepts_auth_date(self.auth_at, now)
+    }
+}
+
+/// A relayer allowed to pay rent and fees for `relayed_store`.
+#[account]
+#[derive(InitSpace)]
+pub struct RelayerConfig {
+    pub relayer: Pubkey,
+    /// Charged to the user for every relayed store.
+    pub store_fee_lamports: u64,
+    pub max_stores_per_wallet: u32,
+    pub bump: u8,
+}
+
+/// What a user wallet has used and owes a relayer.
+///
+/// `stores` counts only `relayed_store` calls, so the relayer's per-wallet limit does
+/// not cap stores the wallet pays for itself through `store`. `owed_lamports` is settled
+/// only by SOL claims in `telegram_transfer` or by `reimburse_relayer`; token claims
+/// leave it owed.
+#[account]
+#[derive(InitSpace)]
+pub struct RelayerUsage {
+    pub relayer: Pubkey,
+    pub user_wallet: Pubkey,
+    pub stores: u32,
+    pub owed_lamports: u64,
+    pub reimbursed_lamports: u64,
+    pub bump: u8,
+}
+
+// ---- Events ----
+
+/// Emitted when initData is stored; the session is not verified yet.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionStored {
+    pub session: Pubkey,
+    pub user_wallet: Pubkey,
+    pub username: String,
+    pub telegram_id: u64,
+    pub auth_at: u64,
+    /// Always `None`: a stored session has not been verified yet.
+    pub verified_at: Option<u64>,
+}
+
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionVerified {
+    pub session: Pubkey,
+    pub user_wallet: Pubkey,
+    pub username: String,
+    pub telegram_id: u64,
+    pub auth_at: u64,
+    pub verified_at: u64,
+    pub verified_until: u64,
+}
+
+/// Emitted by `revoke_session`, and by `close_session` with `closed` set.
+#[event]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionRevoked {
+    pub session: Pubkey,
+    pub user_wallet: Pubkey,
+    pub username: String,
+    pub auth_at: u64,
+    /// When the session was verified, `None` if it was not verified.
+    pub verified_at: Option<u64>,
+    pub revoked_at: u64,
+    pub closed: bool,
+}
+
+/// Any session lifecycle event, decoded from program logs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionEvent {
+    Stored(SessionStored),
+    Verified(SessionVerified),
+    Revoked(SessionRevoked),
+}
+
+impl SessionEvent {
+    /// Decodes the session event logged on a `Program data: ` line.
+    ///
+    /// Returns `None` for lines that are not session events.
+    pub fn from_log(line: &str) -> Option<Self> {
+        Self::decode(&event_log::program_data(line)?)
+    }
+
+    /// Decodes discriminator-prefixed event bytes as written by `emit!`.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        decode_event(data)
+            .map(Self::Stored)
+            .or_else(|| decode_event(data).map(Self::Verified))
+            .or_else(|| decode_event(data).map(Self::Revoked))
+    }
+
+    /// Collects the session events from a transaction's log messages.
+    ///
+    /// Event names are not namespaced, so pass logs of this program's invocations only.
+    pub fn from_logs<'a>(logs: impl IntoIterator<Item = &'a str>) -> Vec<Self> {
+        logs.into_iter().filter_map(Self::from_log).collect()
+    }
+}
+
+// ---- Helpers ----
 
-    let pk_bytes = &data[pk_abs..pk_abs + PUBKEY_LEN];
-    let msg_bytes = &data[msg_abs..msg_abs + message_data_size];
+/// Stores initData issued for `bot_id` and parsed by the caller into an unverified `session`.
+fn store_init_data(
+    session: &mut Account<TelegramSession>,
+    previous_binding: Option<&mut Account<UsernameBinding>>,
+    user_wallet: Pubkey,
+    payer: Pubkey,
+    config: &VerificationConfig,
+    bot_id: u64,
+    parsed: InitData,
+) -> Result<()> {
+    require!(config.bot_ids.contains(&bot_id), ErrorCode::BotIdNotAllowed);
 
-    // 4) Use hardcoded telegram pk
-    let is_telegram_pk = pk_bytes == &TELEGRAM_PUBKEY_PROD;
+    let auth_at = parsed.auth_date;
+    require_fresh_auth_date(auth_at, config)?;
+    release_binding(session, previous_binding)?;
 
-    // 5) Exact-match telegram pk and message
-    require!(is_telegram_pk, ErrorCode::NotVerified);
-    require!(msg_bytes == expected_msg, ErrorCode::NotVerified);
+    // a fresh session; its rent goes back to whoever paid it
+    if session.user_wallet == Pubkey::default() {
+        session.rent_payer = payer;
+    }
+    session.user_wallet = user_wallet;
+    session.bot_id = bot_id;
+    session.payload_hash = parsed.payload_hash();
+    session.username = parsed.username;
+    session.telegram_id = parsed.user.id;
+    session.auth_at = auth_at;
+    session.validation_bytes = parsed.data_check_string;
+    session.clear_verification();
 
+    emit!(SessionStored {
+        session: session.key(),
+        user_wallet,
+        username: session.username.clone(),
+        telegram_id: session.telegram_id,
+        auth_at,
+        verified_at: session.verified_at,
+    });
     Ok(())
 }
 
-/// Extracts username and converts to lowercase
-fn extract_username(bytes: &[u8]) -> Result<String> {
-    // We expect ASCII only.
-    let s = core::str::from_utf8(bytes).map_err(|_| error!(ErrorCode::InvalidTelegramMessage))?;
+/// Releases the username binding held by `session`.
+///
+/// Only a verified session can hold a binding, so one must be passed for it.
+fn release_binding(
+    session: &Account<TelegramSession>,
+    binding: Option<&mut Account<UsernameBinding>>,
+) -> Result<()> {
+    match binding {
+        Some(binding) => binding.release(session.key()),
+        None => require!(!session.verified, ErrorCode::MissingUsernameBinding),
+    }
+    Ok(())
+}
 
-    // Scope to the `user=` payload to avoid matching other `username` keys.
-    let user_start = if let Some(idx) = s.find("\nuser=") {
-        idx + "\nuser=".len()
-    } else if s.starts_with("user=") {
-        "user=".len()
-    } else {
-        return Err(error!(ErrorCode::InvalidTelegramMessage));
-    };
+/// Checks Telegram's signature over the stored initData and, if requested, the
+/// session wallet's attestation.
+fn verify_session_signatures(
+    session: &TelegramSession,
+    config: &VerificationConfig,
+    instructions_ai: &AccountInfo,
+    require_wallet_attestation: bool,
+) -> Result<()> {
+    let attestation =
+        require_wallet_attestation.then(|| wallet_attestation_message(&session.payload_hash));
+    verify_ed25519_signatures(
+        instructions_ai,
+        config,
+        &session.validation_bytes,
+        attestation
+            .as_deref()
+            .map(|message| (&session.user_wallet, message)),
+        Clock::get()?.unix_timestamp as u64,
+    )
+}
 
-    let user_rest = &s[user_start..];
-    let user_end_rel = user_rest.find('\n').unwrap_or(user_rest.len());
-    let user_payload = &user_rest[..user_end_rel];
+/// Marks a session whose signatures were checked as verified, consumes the payload
+/// and binds the username to `session`.
+fn verify_session<'info>(
+    session: &mut Account<'info, TelegramSession>,
+    config: &VerificationConfig,
+    payer: Pubkey,
+    consumed: &mut ConsumedInitData,
+    binding: &mut UsernameBinding,
+    binding_bump: u8,
+    previous_session: Option<&mut Account<'info, TelegramSession>>,
+) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp as u64;
 
-    // Look for `"username":"` within the user payload
-    let start = user_payload
-        .find(USERNAME_PATTERN)
-        .ok_or_else(|| error!(ErrorCode::InvalidTelegramMessage))?
-        + USERNAME_PATTERN.len();
+    require_fresh_auth_date(session.auth_at, config)?;
 
-    let rest = &user_payload[start..];
-    let end_rel = rest
-        .find('"')
-        .ok_or_else(|| error!(ErrorCode::InvalidTelegramMessage))?;
+    // A signed payload can only ever be bound to one wallet.
+    require!(consumed.consumed_at == 0, ErrorCode::Replay);
+    consumed.user_wallet = session.user_wallet;
+    consumed.consumed_at = now;
+    consumed.auth_at = session.auth_at;
+    consumed.payer = payer;
+    consumed.expires_at = session.auth_at.saturating_add(config.max_auth_age_secs);
 
-    let username = &rest[..end_rel];
+    let session_key = session.key();
 
-    require!(
-        (MIN_USERNAME_LEN..=MAX_USERNAME_LEN).contains(&username.len()),
-        ErrorCode::InvalidTelegramUsername
-    );
-    require!(
-        username.bytes().all(|b| (b'A'..=b'Z').contains(&b)
-            || (b'a'..=b'z').contains(&b)
-            || (b'0'..=b'9').contains(&b)
-            || b == b'_'),
-        ErrorCode::InvalidTelegramUsername
-    );
+    // Moving the username to another wallet requires a newer Telegram auth,
+    // and the previous holder's session loses its verification.
+    if binding.session != Pubkey::default() && binding.session != session_key {
+        require!(
+            session.auth_at > binding.auth_at,
+            ErrorCode::StaleUsernameBinding
+        );
+        let previous = previous_session.ok_or_else(|| error!(ErrorCode::InvalidPreviousSession))?;
+        require_keys_eq!(
+            previous.key(),
+            binding.session,
+            ErrorCode::InvalidPreviousSession
+        );
+        if previous.username == binding.username {
+            previous.clear_verification();
+        }
+    }
 
-    Ok(username.to_lowercase())
+    session.verified = true;
+    session.verified_at = Some(now);
+    session.verified_until = Some(now.saturating_add(config.session_ttl_secs));
+
+    binding.username = session.username.clone();
+    binding.user_wallet = session.user_wallet;
+    binding.telegram_id = session.telegram_id;
+    binding.session = session_key;
+    binding.auth_at = session.auth_at;
+    binding.bound_at = now;
+    binding.bump = binding_bump;
+
+    emit!(SessionVerified {
+        session: session_key,
+        user_wallet: session.user_wallet,
+        username: session.username.clone(),
+        telegram_id: session.telegram_id,
+        auth_at: session.auth_at,
+        verified_at: now,
+        verified_until: now.saturating_add(config.session_ttl_secs),
+    });
+    Ok(())
+}
+/// Message a wallet signs to attest it is bound to the initData with `payload_hash`.
+pub fn wallet_attestation_message(payload_hash: &[u8; 32]) -> Vec<u8> {
+    [WALLET_ATTESTATION_PREFIX, payload_hash.as_slice()].concat()
+}
+
+/// Requires an Ed25519 precompile signature by an accepted Telegram key over
+/// `expected_msg` and, when given, one by `wallet` over its attestation message.
+///
+/// Any Ed25519 instruction of the transaction counts, so several verifications
+/// can share one precompile call.
+fn verify_ed25519_signatures(
+    instructions_ai: &AccountInfo,
+    config: &VerificationConfig,
+    expected_msg: &[u8],
+    wallet_attestation: Option<(&Pubkey, &[u8])>,
+    now: u64,
+) -> Result<()> {
+    let mut telegram_signed = false;
+    let mut wallet_signed = wallet_attestation.is_none();
+
+    for_each_ed25519_signature(instructions_ai, |pubkey, message| {
+        if !telegram_signed && message == expected_msg {
+            telegram_signed = config.is_accepted_telegram_key(pubkey, now);
+        }
+        if let Some((wallet, attestation)) = wallet_attestation {
+            wallet_signed |= pubkey == wallet.as_ref() && message == attestation;
+        }
+        !(telegram_signed && wallet_signed)
+    })?;
+
+    require!(telegram_signed, ErrorCode::NotVerified);
+    require!(wallet_signed, ErrorCode::MissingWalletAttestation);
+    Ok(())
 }
 
-fn extract_auth_date(bytes: &[u8]) -> Result<u64> {
-    let s = core::str::from_utf8(bytes).map_err(|_| error!(ErrorCode::InvalidTelegramMessage))?;
+/// Calls `f` with the public key and message of every signature checked by an
+/// Ed25519 precompile instruction in this transaction, until `f` returns false.
+///
+/// The runtime verifies all precompile signatures before the program runs, so
+/// only the referenced bytes need to be read here.
+fn for_each_ed25519_signature(
+    instructions_ai: &AccountInfo,
+    mut f: impl FnMut(&[u8], &[u8]) -> bool,
+) -> Result<()> {
+    let ed25519_id = Pubkey::from_str("Ed25519SigVerify111111111111111111111111111")
+        .map_err(|_| error!(ErrorCode::InvalidEd25519))?;
+
+    let mut index = 0;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions_ai) {
+        index += 1;
+        if ix.program_id != ed25519_id {
+            continue;
+        }
+
+        // Ed25519 ix data = [header(2)] [offsets(14) per signature] [payload...]
+        let data = ix.data.as_slice();
+        require!(data.len() >= ED25519_HEADER_LEN, ErrorCode::InvalidEd25519);
+        let sig_count = data[0] as usize;
+
+        for i in 0..sig_count {
+            let start = ED25519_HEADER_LEN + i * ED25519_OFFSETS_LEN;
+            let offs = data
+                .get(start..start + ED25519_OFFSETS_LEN)
+                .ok_or_else(|| error!(ErrorCode::InvalidEd25519))?;
+            let read_u16 = |i: usize| u16::from_le_bytes([offs[i], offs[i + 1]]);
 
-    let start = if let Some(idx) = s.find(AUTH_DATE_PREFIX) {
-        idx + AUTH_DATE_PREFIX.len()
-    } else if s.starts_with(AUTH_DATE_PREFIX) {
-        AUTH_DATE_PREFIX.len()
-    } else {
-        return Err(error!(ErrorCode::InvalidTelegramMessage));
-    };
+            let public_key_offset = read_u16(4) as usize;
+            let public_key_instruction_index = read_u16(6);
+            let message_data_offset = read_u16(8) as usize;
+            let message_data_size = read_u16(10) as usize;
+            let message_instruction_index = read_u16(12);
 
-    let rest = &s[start..];
-    let end_rel = rest.find('\n').unwrap_or(rest.len());
-    let num_str = &rest[..end_rel];
+            let pk_bytes = ed25519_ix_slice(
+                instructions_ai,
+                data,
+                public_key_instruction_index,
+                public_key_offset,
+                PUBKEY_LEN,
+            )?;
+            let msg_bytes = ed25519_ix_slice(
+                instructions_ai,
+                data,
+                message_instruction_index,
+                message_data_offset,
+                message_data_size,
+            )?;
 
+            if !f(&pk_bytes, &msg_bytes) {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves `len` bytes at `offset` of the instruction an Ed25519 offset entry
+/// points at; `u16::MAX` refers to the precompile instruction itself.
+fn ed25519_ix_slice<'a>(
+    instructions_ai: &AccountInfo,
+    own_data: &'a [u8],
+    instruction_index: u16,
+    offset: usize,
+    len: usize,
+) -> Result<Cow<'a, [u8]>> {
+    if instruction_index == u16::MAX {
+        return own_data
+            .get(offset..offset + len)
+            .map(Cow::Borrowed)
+            .ok_or_else(|| error!(ErrorCode::InvalidEd25519));
+    }
+
+    let ix = load_instruction_at_checked(instruction_index as usize, instructions_ai)
+        .map_err(|_| error!(ErrorCode::InvalidEd25519))?;
+    ix.data
+        .get(offset..offset + len)
+        .map(|bytes| Cow::Owned(bytes.to_vec()))
+        .ok_or_else(|| error!(ErrorCode::InvalidEd25519))
+}
+
+fn require_fresh_auth_date(auth_at: u64, config: &VerificationConfig) -> Result<()> {
+    let now = Clock::get()?.unix_timestamp;
+    require!(now > 0, ErrorCode::InvalidTelegramAuthDate);
     require!(
-        num_str.chars().all(|c| c.is_ascii_digit()),
-        ErrorCode::InvalidTelegramMessage
+        auth_at >= config.auth_date_floor,
+        ErrorCode::ExpiredSignature
     );
 
-    let ts: u64 = num_str
-        .parse()
-        .map_err(|_| error!(ErrorCode::InvalidTelegramMessage))?;
-
-    require!(ts > 0, ErrorCode::InvalidTelegramMessage);
+    init_data::check_auth_date(auth_at, now as u64, config.max_auth_age_secs)?;
+    Ok(())
+}
 
-    Ok(ts as u64)
+impl From<InitDataError> for Error {
+    fn from(e: InitDataError) -> Self {
+        match e {
+            InitDataError::TooLong => error!(ErrorCode::InvalidValidationBytesLength),
+            InitDataError::Malformed => error!(ErrorCode::InvalidTelegramMessage),
+            InitDataError::InvalidUsername => error!(ErrorCode::InvalidTelegramUsername),
+            InitDataError::InvalidAuthDate => error!(ErrorCode::InvalidTelegramAuthDate),
+            InitDataError::Expired => error!(ErrorCode::ExpiredSignature),
+            InitDataError::InvalidSignature => error!(ErrorCode::InvalidTelegramSignature),
+        }
+    }
 }
 
 // ---- Error Codes ----
@@ -295,4 +1539,58 @@ pub enum ErrorCode {
     InvalidTelegramUsername,
     #[msg("Invalid Telegram auth date")]
     InvalidTelegramAuthDate,
+    #[msg("Expired Signature")]
+    ExpiredSignature,
+    #[msg("Replay")]
+    Replay,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Invalid max auth age")]
+    InvalidMaxAuthAge,
+    #[msg("Telegram key not found")]
+    TelegramKeyNotFound,
+    #[msg("Telegram key already registered")]
+    TelegramKeyAlreadyRegistered,
+    #[msg("Telegram key registry full")]
+    TelegramKeyRegistryFull,
+    #[msg("Invalid Telegram key activation window")]
+    InvalidTelegramKeyWindow,
+    #[msg("Username is bound to a more recent Telegram auth")]
+    StaleUsernameBinding,
+    #[msg("Invalid previous session")]
+    InvalidPreviousSession,
+    #[msg("Invalid session TTL")]
+    InvalidSessionTtl,
+    #[msg("Invalid bot id")]
+    InvalidBotId,
+    #[msg("Bot id not allowed")]
+    BotIdNotAllowed,
+    #[msg("Bot id already allowed")]
+    BotIdAlreadyAllowed,
+    #[msg("Bot id allowlist full")]
+    BotIdAllowlistFull,
+    #[msg("Missing wallet attestation signature")]
+    MissingWalletAttestation,
+    #[msg("Invalid relayer store limit")]
+    InvalidRelayerStoreLimit,
+    #[msg("Relayer store limit reached for this wallet")]
+    RelayerStoreLimitReached,
+    #[msg("Invalid relayer")]
+    InvalidRelayer,
+    #[msg("Nothing to reimburse")]
+    NothingToReimburse,
+    #[msg("Overflow")]
+    Overflow,
+    #[msg("Session already migrated")]
+    SessionAlreadyMigrated,
+    #[msg("Consumed initData is still fresh")]
+    ConsumedInitDataStillFresh,
+    #[msg("Username binding of the verified session is missing")]
+    MissingUsernameBinding,
+    #[msg("Invalid rent payer")]
+    InvalidRentPayer,
+    #[msg("Telegram key is not active")]
+    TelegramKeyNotActive,
+    #[msg("Relayer fee outstanding")]
+    RelayerFeeOutstanding,
 }
@@ -0,0 +1,357 @@
+//! Minimal JSON reader for the `user=` field of Telegram initData.
+//!
+//! Only depends on `core` and `alloc` so it can run on-chain. It walks a single
+//! top-level object, decodes the fields we care about (including escapes and
+//! `\uXXXX` sequences) and skips everything else without recursion.
+
+extern crate alloc;
+
+use alloc::string::String;
+
+/// Upper bound on nested objects/arrays inside skipped values.
+const MAX_DEPTH: usize = 16;
+
+/// The subset of Telegram's `WebAppUser` object used by the program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TelegramUser {
+    pub id: u64,
+    pub username: Option<String>,
+    pub first_name: String,
+    pub is_premium: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The input is not a well-formed JSON object.
+    Malformed,
+    /// A field has the wrong JSON type or an out-of-range value.
+    InvalidField(&'static str),
+    /// A required field is absent.
+    MissingField(&'static str),
+    /// A known field appears more than once.
+    DuplicateField(&'static str),
+}
+
+/// Parses the JSON object carried in the `user=` field of initData.
+pub fn parse_telegram_user(json: &[u8]) -> Result<TelegramUser, ParseError> {
+    let mut p = Parser {
+        bytes: json,
+        pos: 0,
+    };
+
+    let mut id = None;
+    let mut username = None;
+    let mut first_name = None;
+    let mut is_premium = None;
+
+    p.skip_ws();
+    p.expect(b'{')?;
+    p.skip_ws();
+    if !p.eat(b'}') {
+        loop {
+            p.skip_ws();
+            let key = p.parse_string()?;
+            p.skip_ws();
+            p.expect(b':')?;
+            p.skip_ws();
+
+            match key.as_str() {
+                "id" => set_once(&mut id, "id", p.parse_user_id()?)?,
+                "username" => {
+                    set_once(&mut username, "username", p.parse_string_field("username")?)?
+                }
+                "first_name" => set_once(
+                    &mut first_name,
+                    "first_name",
+                    p.parse_string_field("first_name")?,
+                )?,
+                "is_premium" => {
+                    set_once(&mut is_premium, "is_premium", p.parse_bool("is_premium")?)?
+                }
+                _ => p.skip_value()?,
+            }
+
+            p.skip_ws();
+            if p.eat(b',') {
+                continue;
+            }
+            p.expect(b'}')?;
+            break;
+        }
+    }
+    p.skip_ws();
+    if p.pos != p.bytes.len() {
+        return Err(ParseError::Malformed);
+    }
+
+    Ok(TelegramUser {
+        id: id.ok_or(ParseError::MissingField("id"))?,
+        username,
+        first_name: first_name.ok_or(ParseError::MissingField("first_name"))?,
+        is_premium: is_premium.unwrap_or(false),
+    })
+}
+
+fn set_once<T>(slot: &mut Option<T>, name: &'static str, value: T) -> Result<(), ParseError> {
+    if slot.is_some() {
+        return Err(ParseError::DuplicateField(name));
+    }
+    *slot = Some(value);
+    Ok(())
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Result<u8, ParseError> {
+        let b = self.peek().ok_or(ParseError::Malformed)?;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn eat(&mut self, b: u8) -> bool {
+        if self.peek() == Some(b) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, b: u8) -> Result<(), ParseError> {
+        if self.eat(b) {
+            Ok(())
+        } else {
+            Err(ParseError::Malformed)
+        }
+    }
+
+    fn eat_literal(&mut self, lit: &[u8]) -> bool {
+        if self.bytes[self.pos..].starts_with(lit) {
+            self.pos += lit.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn parse_string_field(&mut self, name: &'static str) -> Result<String, ParseError> {
+        if self.peek() != Some(b'"') {
+            return Err(ParseError::InvalidField(name));
+        }
+        self.parse_string()
+    }
+
+    fn parse_bool(&mut self, name: &'static str) -> Result<bool, ParseError> {
+        if self.eat_literal(b"true") {
+            Ok(true)
+        } else if self.eat_literal(b"false") {
+            Ok(false)
+        } else {
+            Err(ParseError::InvalidField(name))
+        }
+    }
+
+    /// User ids are positive integers; fractions, exponents and signs are rejected.
+    fn parse_user_id(&mut self) -> Result<u64, ParseError> {
+        let start = self.pos;
+        let mut id: u64 = 0;
+        while let Some(b @ b'0'..=b'9') = self.peek() {
+            id = id
+                .checked_mul(10)
+                .and_then(|v| v.checked_add(u64::from(b - b'0')))
+                .ok_or(ParseError::InvalidField("id"))?;
+            self.pos += 1;
+        }
+        let digits = &self.bytes[start..self.pos];
+        if digits.is_empty()
+            || (digits.len() > 1 && digits[0] == b'0')
+            || matches!(self.peek(), Some(b'.' | b'e' | b'E'))
+            || id == 0
+        {
+            return Err(ParseError::InvalidField("id"));
+        }
+        Ok(id)
+    }
+
+    fn parse_string(&mut self) -> Result<String, ParseError> {
+        self.expect(b'"')?;
+        let mut out = String::new();
+        loop {
+            let start = self.pos;
+            while let Some(b) = self.peek() {
+                if b == b'"' || b == b'\\' || b < 0x20 {
+                    break;
+                }
+                self.pos += 1;
+            }
+            let run = core::str::from_utf8(&self.bytes[start..self.pos])
+                .map_err(|_| ParseError::Malformed)?;
+            out.push_str(run);
+
+            match self.next()? {
+                b'"' => return Ok(out),
+                b'\\' => out.push(self.parse_escape()?),
+                _ => return Err(ParseError::Malformed),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char, ParseError> {
+        Ok(match self.next()? {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let hi = self.parse_hex4()?;
+                let code = match hi {
+                    0xD800..=0xDBFF => {
+                        if !self.eat_literal(b"\\u") {
+                            return Err(ParseError::Malformed);
+                        }
+                        let lo = self.parse_hex4()?;
+                        if !(0xDC00..=0xDFFF).contains(&lo) {
+                            return Err(ParseError::Malformed);
+                        }
+                        0x10000 + ((hi - 0xD800) << 10) + (lo - 0xDC00)
+                    }
+                    0xDC00..=0xDFFF => return Err(ParseError::Malformed),
+                    _ => hi,
+                };
+                char::from_u32(code).ok_or(ParseError::Malformed)?
+            }
+            _ => return Err(ParseError::Malformed),
+        })
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, ParseError> {
+        let mut v = 0u32;
+        for _ in 0..4 {
+            let d = (self.next()? as char)
+                .to_digit(16)
+                .ok_or(ParseError::Malformed)?;
+            v = (v << 4) | d;
+        }
+        Ok(v)
+    }
+
+    fn skip_number(&mut self) -> Result<(), ParseError> {
+        self.eat(b'-');
+        match self.next()? {
+            b'0' => {}
+            b'1'..=b'9' => self.skip_digits(),
+            _ => return Err(ParseError::Malformed),
+        }
+        if self.eat(b'.') {
+            self.skip_digits1()?;
+        }
+        if self.eat(b'e') || self.eat(b'E') {
+            if !self.eat(b'+') {
+                self.eat(b'-');
+            }
+            self.skip_digits1()?;
+        }
+        Ok(())
+    }
+
+    fn skip_digits(&mut self) {
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_digits1(&mut self) -> Result<(), ParseError> {
+        if !matches!(self.peek(), Some(b'0'..=b'9')) {
+            return Err(ParseError::Malformed);
+        }
+        self.skip_digits();
+        Ok(())
+    }
+
+    /// Skips one JSON value of any type, tracking nesting with an explicit stack.
+    fn skip_value(&mut self) -> Result<(), ParseError> {
+        // Closing byte expected for each open container.
+        let mut stack = [0u8; MAX_DEPTH];
+        let mut depth = 0usize;
+
+        loop {
+            // Expect a value.
+            self.skip_ws();
+            match self.peek().ok_or(ParseError::Malformed)? {
+                b'"' => {
+                    self.parse_string()?;
+                }
+                b't' | b'f' => {
+                    self.parse_bool("value")
+                        .map_err(|_| ParseError::Malformed)?;
+                }
+                b'n' => {
+                    if !self.eat_literal(b"null") {
+                        return Err(ParseError::Malformed);
+                    }
+                }
+                b'-' | b'0'..=b'9' => self.skip_number()?,
+                open @ (b'{' | b'[') => {
+                    if depth == MAX_DEPTH {
+                        return Err(ParseError::Malformed);
+                    }
+                    self.pos += 1;
+                    let close = if open == b'{' { b'}' } else { b']' };
+                    stack[depth] = close;
+                    depth += 1;
+                    self.skip_ws();
+                    if !self.eat(close) {
+                        if close == b'}' {
+                            self.skip_key()?;
+                        }
+                        continue;
+                    }
+                    depth -= 1;
+                }
+                _ => return Err(ParseError::Malformed),
+            }
+
+            // After a value: close containers or move to the next element.
+            loop {
+                if depth == 0 {
+                    return Ok(());
+                }
+                self.skip_ws();
+                let close = stack[depth - 1];
+                if self.eat(b',') {
+                    if close == b'}' {
+                        self.skip_ws();
+                        self.skip_key()?;
+                    }
+                    break;
+                }
+                self.expect(close)?;
+                depth -= 1;
+            }
+        }
+    }
+
+    fn skip_key(&mut self) -> Result<(), ParseError> {
+        self.parse_string()?;
+        self.skip_ws();
+        self.expect(b':')
+    }
+}
@@ -0,0 +1,127 @@
+use anchor_lang::prelude::Pubkey;
+use telegram_verification::{ConsumedInitData, ErrorCode, TelegramKey, VerificationConfig};
+
+const AUTH_AT: u64 = 1763598375;
+const MAX_AUTH_AGE: u64 = 300;
+const OLD_KEY: [u8; 32] = [3; 32];
+const NEW_KEY: [u8; 32] = [4; 32];
+
+fn config() -> VerificationConfig {
+    VerificationConfig {
+        authority: Pubkey::new_from_array([1; 32]),
+        max_auth_age_secs: MAX_AUTH_AGE,
+        session_ttl_secs: 86400,
+        bump: 255,
+        telegram_keys: Vec::new(),
+        bot_ids: vec![8065140499],
+        require_wallet_attestation: false,
+        auth_date_floor: 0,
+    }
+}
+
+fn consumed() -> ConsumedInitData {
+    ConsumedInitData {
+        user_wallet: Pubkey::new_from_array([2; 32]),
+        consumed_at: AUTH_AT + 10,
+        auth_at: AUTH_AT,
+        payer: Pubkey::new_from_array([2; 32]),
+        expires_at: AUTH_AT + MAX_AUTH_AGE,
+    }
+}
+
+#[test]
+fn consumed_records_close_after_their_recorded_expiry() {
+    let config = config();
+    let consumed = consumed();
+
+    assert!(!consumed.is_closable(&config, AUTH_AT + MAX_AUTH_AGE));
+    assert!(consumed.is_closable(&config, AUTH_AT + MAX_AUTH_AGE + 1));
+}
+
+#[test]
+fn lowering_max_auth_age_does_not_close_records_early() {
+    let mut config = config();
+    config.set_max_auth_age(60, AUTH_AT + 100);
+
+    assert!(!consumed().is_closable(&config, AUTH_AT + 100));
+}
+
+#[test]
+fn raising_max_auth_age_keeps_closed_payloads_expired() {
+    let mut config = config();
+    let closed_at = AUTH_AT + MAX_AUTH_AGE + 1;
+    assert!(consumed().is_closable(&config, closed_at));
+
+    config.set_max_auth_age(MAX_AUTH_AGE * 10, closed_at + 1);
+    assert!(!config.accepts_auth_date(AUTH_AT, closed_at + 1));
+    // initData issued after the raise gets the full new window
+    assert!(config.accepts_auth_date(closed_at, closed_at + MAX_AUTH_AGE * 5));
+}
+
+#[test]
+fn raising_max_auth_age_extends_payloads_still_fresh() {
+    let mut config = config();
+    let raised_at = AUTH_AT + 100;
+    config.set_max_auth_age(MAX_AUTH_AGE * 10, raised_at);
+
+    let later = AUTH_AT + MAX_AUTH_AGE * 5;
+    assert!(config.accepts_auth_date(AUTH_AT, later));
+    assert!(!consumed().is_closable(&config, later));
+}
+
+fn config_with_key(expires_at: Option<u64>) -> VerificationConfig {
+    let mut config = config();
+    config.telegram_keys.push(TelegramKey {
+        pubkey: OLD_KEY,
+        activated_at: 0,
+        expires_at,
+    });
+    config
+}
+
+#[test]
+fn rotation_keeps_the_old_key_for_the_overlap() {
+    let mut config = config_with_key(None);
+    config
+        .rotate_telegram_key(OLD_KEY, NEW_KEY, 600, AUTH_AT)
+        .unwrap();
+
+    assert!(config.is_accepted_telegram_key(&OLD_KEY, AUTH_AT + 599));
+    assert!(!config.is_accepted_telegram_key(&OLD_KEY, AUTH_AT + 600));
+    assert!(config.is_accepted_telegram_key(&NEW_KEY, AUTH_AT));
+}
+
+#[test]
+fn rotating_a_retired_key_does_not_revive_it() {
+    let mut config = config_with_key(Some(AUTH_AT));
+
+    let err = config
+        .rotate_telegram_key(OLD_KEY, NEW_KEY, 600, AUTH_AT + 1)
+        .err()
+        .unwrap();
+    assert_eq!(err, ErrorCode::TelegramKeyNotActive.into());
+    assert!(!config.is_accepted_telegram_key(&OLD_KEY, AUTH_AT + 1));
+    assert!(!config.is_accepted_telegram_key(&NEW_KEY, AUTH_AT + 1));
+}
+
+#[test]
+fn rotation_never_extends_an_earlier_expiry() {
+    let mut config = config_with_key(Some(AUTH_AT + 60));
+    config
+        .rotate_telegram_key(OLD_KEY, NEW_KEY, 600, AUTH_AT)
+        .unwrap();
+
+    assert!(!config.is_accepted_telegram_key(&OLD_KEY, AUTH_AT + 60));
+}
+
+#[test]
+fn bot_ids_are_allowed_once_and_never_zero() {
+    let mut config = config();
+    config.add_bot_id(42).unwrap();
+    assert_eq!(config.bot_ids, vec![8065140499, 42]);
+
+    let err = config.add_bot_id(42).err().unwrap();
+    assert_eq!(err, ErrorCode::BotIdAlreadyAllowed.into());
+    let err = config.add_bot_id(0).err().unwrap();
+    assert_eq!(err, ErrorCode::InvalidBotId.into());
+}
@@ -0,0 +1,89 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::Event;
+use base64::Engine;
+use telegram_verification::{SessionEvent, SessionRevoked, SessionStored, SessionVerified};
+
+fn log_line(data: &[u8]) -> String {
+    format!(
+        "Program data: {}",
+        base64::engine::general_purpose::STANDARD.encode(data)
+    )
+}
+
+fn verified() -> SessionVerified {
+    SessionVerified {
+        session: Pubkey::new_from_array([1; 32]),
+        user_wallet: Pubkey::new_from_array([2; 32]),
+        username: "dig133713337".to_string(),
+        telegram_id: 8138797767,
+        auth_at: 1763598375,
+        verified_at: 1763598400,
+        verified_until: 1763684800,
+    }
+}
+
+#[test]
+fn decodes_each_event_from_its_log_line() {
+    let stored = SessionStored {
+        session: Pubkey::new_from_array([1; 32]),
+        user_wallet: Pubkey::new_from_array([2; 32]),
+        username: "dig133713337".to_string(),
+        telegram_id: 8138797767,
+        auth_at: 1763598375,
+        verified_at: None,
+    };
+    let revoked = SessionRevoked {
+        session: Pubkey::new_from_array([1; 32]),
+        user_wallet: Pubkey::new_from_array([2; 32]),
+        username: "dig133713337".to_string(),
+        auth_at: 1763598375,
+        verified_at: Some(1763598400),
+        revoked_at: 1763600000,
+        closed: true,
+    };
+    let closed_unverified = SessionRevoked {
+        verified_at: None,
+        ..revoked.clone()
+    };
+
+    assert_eq!(
+        SessionEvent::from_log(&log_line(&stored.data())),
+        Some(SessionEvent::Stored(stored))
+    );
+    assert_eq!(
+        SessionEvent::from_log(&log_line(&verified().data())),
+        Some(SessionEvent::Verified(verified()))
+    );
+    assert_eq!(
+        SessionEvent::from_log(&log_line(&revoked.data())),
+        Some(SessionEvent::Revoked(revoked))
+    );
+    assert_eq!(
+        SessionEvent::from_log(&log_line(&closed_unverified.data())),
+        Some(SessionEvent::Revoked(closed_unverified))
+    );
+}
+
+#[test]
+fn skips_unrelated_log_lines() {
+    let verified_line = log_line(&verified().data());
+    let logs = [
+        "Program 9yiphKYd4b69tR1ZPP8rNwtMeUwWgjYXaXdEzyNziNhz invoke [1]",
+        "Program log: Instruction: VerifyTelegramInitData",
+        "Program data: not-base64!",
+        "Program data: AAAAAAAAAAA=",
+        verified_line.as_str(),
+        "Program 9yiphKYd4b69tR1ZPP8rNwtMeUwWgjYXaXdEzyNziNhz success",
+    ];
+
+    assert_eq!(
+        SessionEvent::from_logs(logs),
+        vec![SessionEvent::Verified(verified())]
+    );
+}
+
+#[test]
+fn rejects_truncated_event_data() {
+    let data = verified().data();
+    assert_eq!(SessionEvent::decode(&data[..data.len() - 1]), None);
+}
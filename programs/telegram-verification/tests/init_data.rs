@@ -0,0 +1,303 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::{InstructionData, ToAccountMetas};
+use base64::Engine;
+use hex_literal::hex;
+use sha2::{Digest, Sha256};
+use telegram_verification::init_data::{
+    build_data_check_string, ed25519_instruction, ed25519_instruction_data, extract_signature,
+    parse_data_check_string, parse_init_data, store_and_verify_ed25519_instruction,
+    verify_init_data, InitDataError, ED25519_PROGRAM_ID, MAX_STORE_AND_VERIFY_LEN,
+    STORE_AND_VERIFY_DATA_CHECK_STRING_OFFSET,
+};
+
+/// Signed data-check-string fixture shared with the TypeScript tests.
+const DATA_CHECK_STRING: &str = "8065140499:WebAppData\nauth_date=1763598375\nchat_instance=-4597807585673845571\nchat_type=sender\nuser={\"id\":8138797767,\"first_name\":\"Travis\",\"last_name\":\"\",\"username\":\"dig133713337\",\"language_code\":\"en\",\"allows_write_to_pm\":true,\"photo_url\":\"https:\\/\\/t.me\\/i\\/userpic\\/320\\/xcZUUUW3uJ2cOPVIQUochiwHc8qvr8jrlB8J-HXxipbSJLzzvxIcOj7g7F1ENtHG.svg\"}";
+const SIGNATURE: [u8; 64] = hex!("8bab39e99101dae31d6a371eedcf1ce516eacaa0dd1fdbfb97b576cfd8fe394fd109b00451e045fdfa6e108f493c233d42b18bb299f80279a131e067be6cea04");
+const TELEGRAM_PUBKEY_PROD: [u8; 32] =
+    hex!("e7bf03a2fa4602af4580703d88dda5bb59f32ed8b02a56c187fe7d34caed242d");
+const BOT_ID: u64 = 8065140499;
+const AUTH_DATE: u64 = 1763598375;
+const MAX_AUTH_AGE_SECS: u64 = 24 * 60 * 60;
+/// Largest serialized transaction the cluster accepts.
+const PACKET_DATA_SIZE: usize = 1232;
+
+fn url_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Raw `Telegram.WebApp.initData` behind the fixture, fields in reverse order.
+fn raw_init_data(extra: &[(&str, &str)]) -> Vec<u8> {
+    let signature = base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(SIGNATURE);
+    let fields: Vec<&str> = DATA_CHECK_STRING.split('\n').skip(1).collect();
+    let mut pairs: Vec<String> = fields
+        .iter()
+        .rev()
+        .map(|field| {
+            let (key, value) = field.split_once('=').unwrap();
+            format!("{key}={}", url_encode(value))
+        })
+        .collect();
+    pairs.push(format!("signature={signature}"));
+    pairs.push("hash=0".to_string());
+    pairs.extend(extra.iter().map(|(k, v)| format!("{k}={}", url_encode(v))));
+    pairs.join("&").into_bytes()
+}
+
+#[test]
+fn parses_raw_init_data_into_the_signed_data_check_string() {
+    let parsed = parse_init_data(BOT_ID, &raw_init_data(&[])).unwrap();
+
+    assert_eq!(parsed.data_check_string, DATA_CHECK_STRING.as_bytes());
+    assert_eq!(parsed.username, "dig133713337");
+    assert_eq!(parsed.user.id, 8138797767);
+    assert_eq!(parsed.user.first_name, "Travis");
+    assert_eq!(parsed.auth_date, AUTH_DATE);
+    assert_eq!(
+        parsed.payload_hash(),
+        <[u8; 32]>::from(Sha256::digest(DATA_CHECK_STRING))
+    );
+}
+
+#[test]
+fn extracts_the_signature_field() {
+    assert_eq!(extract_signature(&raw_init_data(&[])), Ok(SIGNATURE));
+    assert_eq!(
+        extract_signature(b"auth_date=1&hash=0"),
+        Err(InitDataError::InvalidSignature)
+    );
+}
+
+#[test]
+fn verifies_the_telegram_signature_locally() {
+    let parsed = verify_init_data(
+        BOT_ID,
+        &raw_init_data(&[]),
+        &TELEGRAM_PUBKEY_PROD,
+        AUTH_DATE + 10,
+        MAX_AUTH_AGE_SECS,
+    )
+    .unwrap();
+    assert_eq!(parsed.username, "dig133713337");
+}
+
+#[test]
+fn rejects_what_the_program_rejects() {
+    let verify = |bot_id: u64, init_data: &[u8], now: u64| {
+        verify_init_data(
+            bot_id,
+            init_data,
+            &TELEGRAM_PUBKEY_PROD,
+            now,
+            MAX_AUTH_AGE_SECS,
+        )
+    };
+    let raw = raw_init_data(&[]);
+
+    assert_eq!(
+        verify(BOT_ID, &raw, AUTH_DATE + MAX_AUTH_AGE_SECS + 1),
+        Err(InitDataError::Expired)
+    );
+    assert_eq!(
+        verify(BOT_ID, &raw, AUTH_DATE - 61),
+        Err(InitDataError::InvalidAuthDate)
+    );
+    assert_eq!(
+        verify(BOT_ID + 1, &raw, AUTH_DATE),
+        Err(InitDataError::InvalidSignature)
+    );
+    assert_eq!(
+        verify(BOT_ID, &raw_init_data(&[("query_id", "AAH")]), AUTH_DATE),
+        Err(InitDataError::InvalidSignature)
+    );
+    assert_eq!(
+        verify(BOT_ID, &raw_init_data(&[("chat_type", "group")]), AUTH_DATE),
+        Err(InitDataError::Malformed)
+    );
+    assert_eq!(
+        verify(BOT_ID, &raw_init_data(&[("start", "a\nb")]), AUTH_DATE),
+        Err(InitDataError::Malformed)
+    );
+    assert_eq!(
+        verify(BOT_ID, &vec![b'a'; 1025], AUTH_DATE),
+        Err(InitDataError::TooLong)
+    );
+}
+
+#[test]
+fn rejects_malformed_query_strings() {
+    for raw in [&b"auth_date=1&broken"[..], b"=1", b"auth_date=%zz", b"a=%4"] {
+        assert_eq!(
+            build_data_check_string(BOT_ID, raw),
+            Err(InitDataError::Malformed),
+            "{}",
+            String::from_utf8_lossy(raw)
+        );
+    }
+}
+
+#[test]
+fn builds_the_web3_ed25519_instruction_layout() {
+    let message = DATA_CHECK_STRING.as_bytes();
+    let data = ed25519_instruction_data(&TELEGRAM_PUBKEY_PROD, &SIGNATURE, message);
+
+    let len = (message.len() as u16).to_le_bytes();
+    assert_eq!(
+        data[..16],
+        [1, 0, 48, 0, 0xff, 0xff, 16, 0, 0xff, 0xff, 112, 0, len[0], len[1], 0xff, 0xff]
+    );
+    assert_eq!(data[16..48], TELEGRAM_PUBKEY_PROD);
+    assert_eq!(data[48..112], SIGNATURE);
+    assert_eq!(&data[112..], message);
+
+    let ix = ed25519_instruction(&TELEGRAM_PUBKEY_PROD, &SIGNATURE, message);
+    assert_eq!(ix.program_id, ED25519_PROGRAM_ID);
+    assert!(ix.accounts.is_empty());
+    assert_eq!(ix.data, data);
+}
+
+#[test]
+fn parses_the_signed_data_check_string_like_raw_init_data() {
+    assert_eq!(
+        parse_data_check_string(BOT_ID, DATA_CHECK_STRING.as_bytes()),
+        parse_init_data(BOT_ID, &raw_init_data(&[]))
+    );
+}
+
+#[test]
+fn rejects_data_check_strings_telegram_would_not_sign() {
+    let fields: Vec<&str> = DATA_CHECK_STRING.split('\n').skip(1).collect();
+    let with_fields = |fields: &[&str]| format!("{BOT_ID}:WebAppData\n{}", fields.join("\n"));
+
+    let mut unsorted = fields.clone();
+    unsorted.swap(0, 1);
+    let duplicated = [&fields[..], &fields[3..]].concat();
+    let with_hash = [&fields[..], &["hash=0"]].concat();
+
+    for data_check_string in [
+        with_fields(&unsorted),
+        with_fields(&duplicated),
+        with_fields(&with_hash),
+        with_fields(&["auth_date"]),
+        DATA_CHECK_STRING.replacen("8065140499", "8065140498", 1),
+        format!("{BOT_ID}:WebAppData"),
+    ] {
+        assert_eq!(
+            parse_data_check_string(BOT_ID, data_check_string.as_bytes()),
+            Err(InitDataError::Malformed),
+            "{data_check_string}"
+        );
+    }
+}
+
+/// Serialized size of a `store_and_verify` transaction for `data_check_string`,
+/// with a separate payer, a maximal username and every optional account passed.
+fn store_and_verify_transaction_len(data_check_string: &[u8]) -> usize {
+    let key = |seed: u8| Pubkey::new_from_array([seed; 32]);
+    let payer = key(1);
+    let store_and_verify = Instruction {
+        program_id: telegram_verification::ID,
+        accounts: telegram_verification::accounts::StoreAndVerifyTelegramInitData {
+            payer,
+            user: key(2),
+            config: key(3),
+            session: key(4),
+            previous_binding: Some(key(5)),
+            consumed: key(6),
+            binding: key(7),
+            previous_session: Some(key(8)),
+            instructions: anchor_lang::solana_program::sysvar::instructions::ID,
+            system_program: anchor_lang::solana_program::system_program::ID,
+        }
+        .to_account_metas(None),
+        data: telegram_verification::instruction::StoreAndVerify {
+            bot_id: BOT_ID,
+            data_check_string: data_check_string.to_vec(),
+            payload_hash: Sha256::digest(data_check_string).into(),
+            username: "a".repeat(32),
+        }
+        .data(),
+    };
+    let offset = STORE_AND_VERIFY_DATA_CHECK_STRING_OFFSET as usize;
+    assert_eq!(
+        &store_and_verify.data[offset..offset + data_check_string.len()],
+        data_check_string
+    );
+
+    let ed25519 = store_and_verify_ed25519_instruction(
+        &TELEGRAM_PUBKEY_PROD,
+        &SIGNATURE,
+        1,
+        data_check_string.len() as u16,
+    );
+    legacy_transaction_len(&[ed25519, store_and_verify], payer)
+}
+
+/// Serialized size of a legacy transaction, as laid out by `Message::new`.
+fn legacy_transaction_len(instructions: &[Instruction], payer: Pubkey) -> usize {
+    let compact_len = |n: usize| match n {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    };
+
+    let mut keys = vec![payer];
+    let mut signers = vec![payer];
+    for ix in instructions {
+        for meta in &ix.accounts {
+            if !keys.contains(&meta.pubkey) {
+                keys.push(meta.pubkey);
+            }
+            if meta.is_signer && !signers.contains(&meta.pubkey) {
+                signers.push(meta.pubkey);
+            }
+        }
+        if !keys.contains(&ix.program_id) {
+            keys.push(ix.program_id);
+        }
+    }
+
+    let instructions_len: usize = instructions
+        .iter()
+        .map(|ix| {
+            1 + compact_len(ix.accounts.len())
+                + ix.accounts.len()
+                + compact_len(ix.data.len())
+                + ix.data.len()
+        })
+        .sum();
+
+    // signatures, header, account keys, recent blockhash, instructions
+    compact_len(signers.len())
+        + 64 * signers.len()
+        + 3
+        + compact_len(keys.len())
+        + 32 * keys.len()
+        + 32
+        + compact_len(instructions.len())
+        + instructions_len
+}
+
+#[test]
+fn real_init_data_fits_one_store_and_verify_transaction() {
+    let parsed = parse_init_data(BOT_ID, &raw_init_data(&[])).unwrap();
+    assert!(parsed.data_check_string.len() <= MAX_STORE_AND_VERIFY_LEN);
+    assert!(store_and_verify_transaction_len(&parsed.data_check_string) <= PACKET_DATA_SIZE);
+
+    assert_eq!(
+        store_and_verify_transaction_len(&vec![b'a'; MAX_STORE_AND_VERIFY_LEN]),
+        PACKET_DATA_SIZE
+    );
+    assert!(
+        store_and_verify_transaction_len(&vec![b'a'; MAX_STORE_AND_VERIFY_LEN + 1])
+            > PACKET_DATA_SIZE
+    );
+}
@@ -0,0 +1,50 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_lang::{AccountSerialize, AnchorSerialize, Discriminator, Space};
+use telegram_verification::init_data::{MAX_USERNAME_LEN, MAX_VALIDATION_LEN};
+use telegram_verification::{ErrorCode, TelegramSession};
+
+const USERNAME: &str = "dig133713337";
+const AUTH_AT: u64 = 1763598375;
+const VALIDATION_BYTES: &[u8] = b"8065140499:WebAppData\nauth_date=1763598375";
+
+/// Size the program allocated for sessions before expiry and replay protection.
+const LEGACY_SESSION_SPACE: usize =
+    8 + 32 + (4 + MAX_USERNAME_LEN) + (4 + MAX_VALIDATION_LEN) + 1 + 8 + 9;
+
+/// A session verified under the legacy layout, which had no expiry or replay record.
+fn legacy_session_account() -> Vec<u8> {
+    let fields = (
+        Pubkey::new_from_array([2; 32]),
+        USERNAME.to_string(),
+        VALIDATION_BYTES.to_vec(),
+        true,
+        AUTH_AT,
+        Some(1763598400u64),
+    );
+    let mut data = TelegramSession::DISCRIMINATOR.to_vec();
+    data.extend(fields.try_to_vec().unwrap());
+    data.resize(LEGACY_SESSION_SPACE, 0);
+    data
+}
+
+#[test]
+fn legacy_sessions_migrate_unverified_and_keep_their_init_data() {
+    let session = TelegramSession::try_from_legacy(&legacy_session_account()).unwrap();
+
+    assert_eq!(session.user_wallet, Pubkey::new_from_array([2; 32]));
+    assert_eq!(session.username, USERNAME);
+    assert_eq!(session.validation_bytes, VALIDATION_BYTES);
+    assert_eq!(session.auth_at, AUTH_AT);
+    assert!(!session.verified);
+    assert_eq!(session.verified_at, None);
+    assert_eq!(session.telegram_id, 0);
+    assert_eq!(session.rent_payer, session.user_wallet);
+    assert_eq!(session.relayer, None);
+    assert!(!session.is_verified(AUTH_AT));
+
+    let mut data = Vec::new();
+    session.try_serialize(&mut data).unwrap();
+    data.resize(8 + TelegramSession::INIT_SPACE, 0);
+    let err = TelegramSession::try_from_legacy(&data).err().unwrap();
+    assert_eq!(err, ErrorCode::SessionAlreadyMigrated.into());
+}
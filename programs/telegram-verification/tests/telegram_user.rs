@@ -0,0 +1,278 @@
+use telegram_verification::telegram_user::{parse_telegram_user, ParseError};
+use telegram_verification::TelegramUser;
+
+/// `user=` payload from the signed initData in `tests/telegram-verification-gasless.ts`.
+const GASLESS_USER: &str = r#"{"id":8138797767,"first_name":"Travis","last_name":"","username":"dig133713337","language_code":"en","allows_write_to_pm":true,"photo_url":"https:\/\/t.me\/i\/userpic\/320\/xcZUUUW3uJ2cOPVIQUochiwHc8qvr8jrlB8J-HXxipbSJLzzvxIcOj7g7F1ENtHG.svg"}"#;
+
+/// Same user as sent by a premium account with extra, nested fields.
+const PREMIUM_USER: &str = r#"{"id":8138797767,"is_bot":false,"first_name":"Travis 🚀","last_name":"","username":"dig133713337","language_code":"en","is_premium":true,"added_to_attachment_menu":false,"allows_write_to_pm":true,"extra":{"list":[1,-2.5e3,null,"x",{"a":[[]]}]}}"#;
+
+/// Fields of the gasless fixture as (key, raw JSON value) pairs.
+const GASLESS_FIELDS: &[(&str, &str)] = &[
+    ("id", "8138797767"),
+    ("first_name", r#""Travis""#),
+    ("last_name", r#""""#),
+    ("username", r#""dig133713337""#),
+    ("language_code", r#""en""#),
+    ("allows_write_to_pm", "true"),
+    (
+        "photo_url",
+        r#""https:\/\/t.me\/i\/userpic\/320\/xcZUUUW3uJ2cOPVIQUochiwHc8qvr8jrlB8J-HXxipbSJLzzvxIcOj7g7F1ENtHG.svg""#,
+    ),
+];
+
+fn gasless_user() -> TelegramUser {
+    TelegramUser {
+        id: 8138797767,
+        username: Some("dig133713337".to_string()),
+        first_name: "Travis".to_string(),
+        is_premium: false,
+    }
+}
+
+/// Small deterministic PRNG so the property cases are reproducible without extra deps.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+fn whitespace(rng: &mut XorShift) -> &'static str {
+    [" ", "", "\n", "\t ", "\r\n", ""][rng.below(6)]
+}
+
+/// Re-encodes a plain JSON string literal, escaping random characters as `\uXXXX`.
+fn escape_randomly(rng: &mut XorShift, literal: &str) -> String {
+    let inner = &literal[1..literal.len() - 1];
+    if inner.contains('\\') {
+        return literal.to_string();
+    }
+    let mut out = String::from("\"");
+    for c in inner.chars() {
+        if rng.below(3) == 0 {
+            let mut units = [0u16; 2];
+            for unit in c.encode_utf16(&mut units) {
+                out.push_str(&format!("\\u{:04x}", unit));
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn render(rng: &mut XorShift, fields: &[(&str, &str)]) -> String {
+    let mut out = String::from(whitespace(rng));
+    out.push('{');
+    for (i, (key, value)) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let value = if value.starts_with('"') {
+            escape_randomly(rng, value)
+        } else {
+            value.to_string()
+        };
+        let key = escape_randomly(rng, &format!("\"{key}\""));
+        out.push_str(&format!(
+            "{}{}{}:{}{}{}",
+            whitespace(rng),
+            key,
+            whitespace(rng),
+            whitespace(rng),
+            value,
+            whitespace(rng)
+        ));
+    }
+    out.push('}');
+    out.push_str(whitespace(rng));
+    out
+}
+
+#[test]
+fn parses_gasless_fixture() {
+    assert_eq!(
+        parse_telegram_user(GASLESS_USER.as_bytes()),
+        Ok(gasless_user())
+    );
+}
+
+#[test]
+fn parses_premium_user_with_nested_fields_and_surrogates() {
+    let user = parse_telegram_user(PREMIUM_USER.as_bytes()).unwrap();
+    assert_eq!(user.id, 8138797767);
+    assert_eq!(user.first_name, "Travis \u{1F680}");
+    assert_eq!(user.username.as_deref(), Some("dig133713337"));
+    assert!(user.is_premium);
+}
+
+#[test]
+fn username_is_optional() {
+    let user = parse_telegram_user(br#"{"id":1,"first_name":"A"}"#).unwrap();
+    assert_eq!(user.username, None);
+    assert!(!user.is_premium);
+}
+
+#[test]
+fn decodes_escapes_in_username() {
+    let user =
+        parse_telegram_user(br#"{"id":1,"first_name":"\"A\\","username":"dig_1337"}"#).unwrap();
+    assert_eq!(user.first_name, r#""A\"#);
+    assert_eq!(user.username.as_deref(), Some("dig_1337"));
+}
+
+#[test]
+fn username_lookalike_in_other_fields_is_ignored() {
+    let json = br#"{"first_name":"\"username\":\"evil_user","id":7,"username":"real_user"}"#;
+    let user = parse_telegram_user(json).unwrap();
+    assert_eq!(user.username.as_deref(), Some("real_user"));
+}
+
+#[test]
+fn rejects_invalid_users() {
+    let cases: &[(&[u8], ParseError)] = &[
+        (br#"{"first_name":"A"}"#, ParseError::MissingField("id")),
+        (br#"{"id":1}"#, ParseError::MissingField("first_name")),
+        (
+            br#"{"id":1,"id":2,"first_name":"A"}"#,
+            ParseError::DuplicateField("id"),
+        ),
+        (
+            br#"{"id":1,"first_name":"A","username":"a","username":"b"}"#,
+            ParseError::DuplicateField("username"),
+        ),
+        (
+            br#"{"id":-1,"first_name":"A"}"#,
+            ParseError::InvalidField("id"),
+        ),
+        (
+            br#"{"id":0,"first_name":"A"}"#,
+            ParseError::InvalidField("id"),
+        ),
+        (
+            br#"{"id":01,"first_name":"A"}"#,
+            ParseError::InvalidField("id"),
+        ),
+        (
+            br#"{"id":1.5,"first_name":"A"}"#,
+            ParseError::InvalidField("id"),
+        ),
+        (
+            br#"{"id":1e3,"first_name":"A"}"#,
+            ParseError::InvalidField("id"),
+        ),
+        (
+            br#"{"id":"1","first_name":"A"}"#,
+            ParseError::InvalidField("id"),
+        ),
+        (
+            br#"{"id":18446744073709551616,"first_name":"A"}"#,
+            ParseError::InvalidField("id"),
+        ),
+        (
+            br#"{"id":1,"first_name":"A","username":null}"#,
+            ParseError::InvalidField("username"),
+        ),
+        (
+            br#"{"id":1,"first_name":"A","is_premium":1}"#,
+            ParseError::InvalidField("is_premium"),
+        ),
+        (br#"{"id":1,"first_name":"A"} x"#, ParseError::Malformed),
+        (br#"{"id":1,"first_name":"A",}"#, ParseError::Malformed),
+        (br#"{"id":1,"first_name":"\ud83d"}"#, ParseError::Malformed),
+        (br#"{"id":1,"first_name":"\x"}"#, ParseError::Malformed),
+        (b"{\"id\":1,\"first_name\":\"a\nb\"}", ParseError::Malformed),
+        (br#"[{"id":1,"first_name":"A"}]"#, ParseError::Malformed),
+    ];
+    for (json, expected) in cases {
+        assert_eq!(
+            parse_telegram_user(json),
+            Err(*expected),
+            "{}",
+            String::from_utf8_lossy(json)
+        );
+    }
+}
+
+#[test]
+fn rejects_deeply_nested_values() {
+    let json = format!(
+        r#"{{"id":1,"first_name":"A","x":{}{}}}"#,
+        "[".repeat(64),
+        "]".repeat(64)
+    );
+    assert_eq!(
+        parse_telegram_user(json.as_bytes()),
+        Err(ParseError::Malformed)
+    );
+}
+
+#[test]
+fn property_field_order_whitespace_and_escapes_do_not_matter() {
+    let mut rng = XorShift(0x5eed_1e55_c0ff_ee00);
+    let mut fields = GASLESS_FIELDS.to_vec();
+    for _ in 0..2_000 {
+        rng.shuffle(&mut fields);
+        let json = render(&mut rng, &fields);
+        assert_eq!(
+            parse_telegram_user(json.as_bytes()),
+            Ok(gasless_user()),
+            "{json}"
+        );
+    }
+}
+
+#[test]
+fn property_truncated_payloads_are_rejected() {
+    for fixture in [GASLESS_USER, PREMIUM_USER] {
+        for end in 0..fixture.len() {
+            assert!(
+                parse_telegram_user(&fixture.as_bytes()[..end]).is_err(),
+                "{}",
+                &fixture[..end]
+            );
+        }
+    }
+}
+
+#[test]
+fn fuzz_mutated_payloads_never_panic() {
+    const ALPHABET: &[u8] = br#"{}[]":,\u0123456789abcdefntr -.eE "#;
+    let mut rng = XorShift(0x0dd_ba11_cafe_f00d);
+    for fixture in [GASLESS_USER, PREMIUM_USER] {
+        for _ in 0..20_000 {
+            let mut bytes = fixture.as_bytes().to_vec();
+            for _ in 0..=rng.below(4) {
+                let i = rng.below(bytes.len());
+                match rng.below(4) {
+                    0 => bytes[i] = ALPHABET[rng.below(ALPHABET.len())],
+                    1 => bytes[i] = rng.next() as u8,
+                    2 => {
+                        bytes.remove(i);
+                    }
+                    _ => bytes.insert(i, ALPHABET[rng.below(ALPHABET.len())]),
+                }
+            }
+            // Anything that does parse must still carry a usable id.
+            if let Ok(user) = parse_telegram_user(&bytes) {
+                assert!(user.id > 0);
+            }
+        }
+    }
+}
@@ -711,7 +711,7 @@ export class LoyalPrivateTransactionsClient {
     const {
       username,
       tokenMint,
-      // session,
+      session,
       payer,
       validator,
       rpcOptions,
@@ -731,7 +731,7 @@ export class LoyalPrivateTransactionsClient {
 
     const accounts: Record<string, PublicKey | null> = {
       payer,
-      // session,
+      session: session ?? null,
       bufferDeposit: bufferPda,
       delegationRecordDeposit: delegationRecordPda,
       delegationMetadataDeposit: delegationMetadataPda,
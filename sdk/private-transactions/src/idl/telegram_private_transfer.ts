@@ -13,121 +13,628 @@ export type TelegramPrivateTransfer = {
     "description": "Created with Anchor"
   },
   "instructions": [
+    {
+      "name": "auditVault",
+      "docs": [
+        "Checks that the vault token account covers every deposit of its mint.",
+        "",
+        "Permissionless; emits `VaultAudited` and fails when the vault is insolvent."
+      ],
+      "discriminator": [
+        130,
+        167,
+        148,
+        121,
+        116,
+        63,
+        191,
+        131
+      ],
+      "accounts": [
+        {
+          "name": "vault",
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  118,
+                  97,
+                  117,
+                  108,
+                  116
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "tokenMint"
+              }
+            ]
+          }
+        },
+        {
+          "name": "vaultTokenAccount",
+          "pda": {
+            "seeds": [
+              {
+                "kind": "account",
+                "path": "vault"
+              },
+              {
+                "kind": "account",
+                "path": "tokenProgram"
+              },
+              {
+                "kind": "account",
+                "path": "tokenMint"
+              }
+            ],
+            "program": {
+              "kind": "const",
+              "value": [
+                140,
+                151,
+                37,
+                143,
+                78,
+                36,
+                137,
+                241,
+                187,
+                61,
+                16,
+                41,
+                20,
+                142,
+                13,
+                131,
+                11,
+                90,
+                19,
+                153,
+                218,
+                255,
+                16,
+                132,
+                4,
+                142,
+                123,
+                216,
+                219,
+                233,
+                248,
+                89
+              ]
+            }
+          }
+        },
+        {
+          "name": "tokenMint"
+        },
+        {
+          "name": "tokenProgram"
+        }
+      ],
+      "args": []
+    },
     {
       "name": "claimUsernameDepositToDeposit",
       "docs": [
-        "Claim tokens and transfer from username deposit to deposit"
+        "Claim tokens and transfer from username deposit to deposit"
+      ],
+      "discriminator": [
+        147,
+        77,
+        235,
+        126,
+        72,
+        182,
+        30,
+        12
+      ],
+      "accounts": [
+        {
+          "name": "user",
+          "relations": [
+            "destinationDeposit"
+          ]
+        },
+        {
+          "name": "sourceUsernameDeposit",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  117,
+                  115,
+                  101,
+                  114,
+                  110,
+                  97,
+                  109,
+                  101,
+                  95,
+                  100,
+                  101,
+                  112,
+                  111,
+                  115,
+                  105,
+                  116
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "source_username_deposit.username",
+                "account": "usernameDeposit"
+              },
+              {
+                "kind": "account",
+                "path": "source_username_deposit.token_mint",
+                "account": "usernameDeposit"
+              }
+            ]
+          }
+        },
+        {
+          "name": "destinationDeposit",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  100,
+                  101,
+                  112,
+                  111,
+                  115,
+                  105,
+                  116,
+                  95,
+                  118,
+                  50
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "destination_deposit.user",
+                "account": "deposit"
+              },
+              {
+                "kind": "account",
+                "path": "destination_deposit.token_mint",
+                "account": "deposit"
+              }
+            ]
+          }
+        },
+        {
+          "name": "tokenMint",
+          "relations": [
+            "sourceUsernameDeposit",
+            "destinationDeposit"
+          ]
+        },
+        {
+          "name": "session"
+        },
+        {
+          "name": "tokenProgram"
+        }
+      ],
+      "args": [
+        {
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "claimUsernameDepositToWallet",
+      "docs": [
+        "Claim tokens from a username deposit straight to the recipient's token account.",
+        "",
+        "For the native mint the recipient's wSOL account is closed afterwards, so the",
+        "claim (and any wSOL already held there) arrives as SOL."
+      ],
+      "discriminator": [
+        204,
+        38,
+        200,
+        213,
+        35,
+        149,
+        147,
+        133
+      ],
+      "accounts": [
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "user",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "vault",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  118,
+                  97,
+                  117,
+                  108,
+                  116
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "tokenMint"
+              }
+            ]
+          }
+        },
+        {
+          "name": "usernameDeposit",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  117,
+                  115,
+                  101,
+                  114,
+                  110,
+                  97,
+                  109,
+                  101,
+                  95,
+                  100,
+                  101,
+                  112,
+                  111,
+                  115,
+                  105,
+                  116
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "username_deposit.username",
+                "account": "usernameDeposit"
+              },
+              {
+                "kind": "account",
+                "path": "username_deposit.token_mint",
+                "account": "usernameDeposit"
+              }
+            ]
+          }
+        },
+        {
+          "name": "session"
+        },
+        {
+          "name": "userTokenAccount",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "account",
+                "path": "user"
+              },
+              {
+                "kind": "account",
+                "path": "tokenProgram"
+              },
+              {
+                "kind": "account",
+                "path": "tokenMint"
+              }
+            ],
+            "program": {
+              "kind": "const",
+              "value": [
+                140,
+                151,
+                37,
+                143,
+                78,
+                36,
+                137,
+                241,
+                187,
+                61,
+                16,
+                41,
+                20,
+                142,
+                13,
+                131,
+                11,
+                90,
+                19,
+                153,
+                218,
+                255,
+                16,
+                132,
+                4,
+                142,
+                123,
+                216,
+                219,
+                233,
+                248,
+                89
+              ]
+            }
+          }
+        },
+        {
+          "name": "vaultTokenAccount",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "account",
+                "path": "vault"
+              },
+              {
+                "kind": "account",
+                "path": "tokenProgram"
+              },
+              {
+                "kind": "account",
+                "path": "tokenMint"
+              }
+            ],
+            "program": {
+              "kind": "const",
+              "value": [
+                140,
+                151,
+                37,
+                143,
+                78,
+                36,
+                137,
+                241,
+                187,
+                61,
+                16,
+                41,
+                20,
+                142,
+                13,
+                131,
+                11,
+                90,
+                19,
+                153,
+                218,
+                255,
+                16,
+                132,
+                4,
+                142,
+                123,
+                216,
+                219,
+                233,
+                248,
+                89
+              ]
+            }
+          }
+        },
+        {
+          "name": "tokenMint",
+          "relations": [
+            "usernameDeposit"
+          ]
+        },
+        {
+          "name": "tokenProgram"
+        },
+        {
+          "name": "associatedTokenProgram",
+          "address": "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL"
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "closeDeposit",
+      "docs": [
+        "Closes an empty deposit and its permission, returning each one's rent to whoever paid for it.",
+        "",
+        "The permission program refunds only a signer, so the permission's rent passes through",
+        "the owner on its way to the permission's payer. A delegated deposit is owned by the",
+        "delegation program and fails to load here, so undelegate it first."
       ],
       "discriminator": [
-        147,
-        77,
-        235,
-        126,
-        72,
-        182,
-        30,
-        12
+        200,
+        19,
+        254,
+        192,
+        15,
+        110,
+        209,
+        179
       ],
       "accounts": [
         {
           "name": "user",
+          "writable": true,
+          "signer": true,
           "relations": [
-            "destinationDeposit"
+            "deposit"
           ]
         },
         {
-          "name": "sourceUsernameDeposit",
+          "name": "rentReceiver",
+          "writable": true
+        },
+        {
+          "name": "deposit",
           "writable": true,
           "pda": {
             "seeds": [
               {
                 "kind": "const",
                 "value": [
-                  117,
-                  115,
-                  101,
-                  114,
-                  110,
-                  97,
-                  109,
-                  101,
-                  95,
                   100,
                   101,
                   112,
                   111,
                   115,
                   105,
-                  116
+                  116,
+                  95,
+                  118,
+                  50
                 ]
               },
               {
                 "kind": "account",
-                "path": "source_username_deposit.username",
-                "account": "usernameDeposit"
+                "path": "user"
               },
               {
                 "kind": "account",
-                "path": "source_username_deposit.token_mint",
-                "account": "usernameDeposit"
+                "path": "deposit.token_mint",
+                "account": "deposit"
               }
             ]
           }
         },
         {
-          "name": "destinationDeposit",
+          "name": "permission",
+          "writable": true
+        },
+        {
+          "name": "permissionRentReceiver",
+          "writable": true
+        },
+        {
+          "name": "permissionProgram",
+          "address": "ACLseoPoyC3cBqoUtkbjZ4aDrkurZW86v19pXz2XQnp1"
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "closeUsernameDeposit",
+      "docs": [
+        "Closes an empty username deposit and its permission, returning each one's rent to whoever paid for it.",
+        "",
+        "Only the verified owner of the username can close it. As in `close_deposit`, the",
+        "permission's rent passes through them on its way to the permission's payer."
+      ],
+      "discriminator": [
+        238,
+        181,
+        185,
+        209,
+        149,
+        161,
+        124,
+        79
+      ],
+      "accounts": [
+        {
+          "name": "authority",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "rentReceiver",
+          "writable": true
+        },
+        {
+          "name": "deposit",
           "writable": true,
           "pda": {
             "seeds": [
               {
                 "kind": "const",
                 "value": [
+                  117,
+                  115,
+                  101,
+                  114,
+                  110,
+                  97,
+                  109,
+                  101,
+                  95,
                   100,
                   101,
                   112,
                   111,
                   115,
                   105,
-                  116,
-                  95,
-                  118,
-                  50
+                  116
                 ]
               },
               {
                 "kind": "account",
-                "path": "destination_deposit.user",
-                "account": "deposit"
+                "path": "deposit.username",
+                "account": "usernameDeposit"
               },
               {
                 "kind": "account",
-                "path": "destination_deposit.token_mint",
-                "account": "deposit"
+                "path": "deposit.token_mint",
+                "account": "usernameDeposit"
               }
             ]
           }
         },
         {
-          "name": "tokenMint",
-          "relations": [
-            "sourceUsernameDeposit",
-            "destinationDeposit"
-          ]
+          "name": "session"
         },
         {
-          "name": "session"
+          "name": "permission",
+          "writable": true
         },
         {
-          "name": "tokenProgram",
-          "address": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
-        }
-      ],
-      "args": [
+          "name": "permissionRentReceiver",
+          "writable": true
+        },
         {
-          "name": "amount",
-          "type": "u64"
+          "name": "permissionProgram",
+          "address": "ACLseoPoyC3cBqoUtkbjZ4aDrkurZW86v19pXz2XQnp1"
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
         }
-      ]
+      ],
+      "args": []
     },
     {
       "name": "createPermission",
@@ -158,6 +665,7 @@ export type TelegramPrivateTransfer = {
         },
         {
           "name": "deposit",
+          "writable": true,
           "pda": {
             "seeds": [
               {
@@ -228,6 +736,7 @@ export type TelegramPrivateTransfer = {
         },
         {
           "name": "deposit",
+          "writable": true,
           "pda": {
             "seeds": [
               {
@@ -497,7 +1006,10 @@ export type TelegramPrivateTransfer = {
     {
       "name": "delegateUsernameDeposit",
       "docs": [
-        "Delegates the username-based deposit account to the ephemeral rollups delegate program."
+        "Delegates the username-based deposit account to the ephemeral rollups delegate program.",
+        "",
+        "The verified owner of the username may pick any validator; without their",
+        "session the validator must be one of `ALLOWED_VALIDATORS`."
       ],
       "discriminator": [
         26,
@@ -519,6 +1031,10 @@ export type TelegramPrivateTransfer = {
           "name": "validator",
           "optional": true
         },
+        {
+          "name": "session",
+          "optional": true
+        },
         {
           "name": "bufferDeposit",
           "writable": true,
@@ -641,15 +1157,165 @@ export type TelegramPrivateTransfer = {
               },
               {
                 "kind": "account",
-                "path": "deposit"
+                "path": "deposit"
+              }
+            ],
+            "program": {
+              "kind": "account",
+              "path": "delegationProgram"
+            }
+          }
+        },
+        {
+          "name": "deposit",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  117,
+                  115,
+                  101,
+                  114,
+                  110,
+                  97,
+                  109,
+                  101,
+                  95,
+                  100,
+                  101,
+                  112,
+                  111,
+                  115,
+                  105,
+                  116
+                ]
+              },
+              {
+                "kind": "arg",
+                "path": "username"
+              },
+              {
+                "kind": "arg",
+                "path": "tokenMint"
+              }
+            ]
+          }
+        },
+        {
+          "name": "ownerProgram",
+          "address": "97FzQdWi26mFNR21AbQNg4KqofiCLqQydQfAvRQMcXhV"
+        },
+        {
+          "name": "delegationProgram",
+          "address": "DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh"
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "username",
+          "type": "string"
+        },
+        {
+          "name": "tokenMint",
+          "type": "pubkey"
+        }
+      ]
+    },
+    {
+      "name": "initializeDeposit",
+      "docs": [
+        "Initializes a deposit account for a user and token mint if it does not exist.",
+        "",
+        "Sets up a new deposit account with zero balance for the user and token mint.",
+        "If the account is already initialized, this instruction is a no-op."
+      ],
+      "discriminator": [
+        171,
+        65,
+        93,
+        225,
+        61,
+        109,
+        31,
+        227
+      ],
+      "accounts": [
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "user"
+        },
+        {
+          "name": "deposit",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  100,
+                  101,
+                  112,
+                  111,
+                  115,
+                  105,
+                  116,
+                  95,
+                  118,
+                  50
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "user"
+              },
+              {
+                "kind": "account",
+                "path": "tokenMint"
               }
-            ],
-            "program": {
-              "kind": "account",
-              "path": "delegationProgram"
-            }
+            ]
           }
         },
+        {
+          "name": "tokenMint"
+        },
+        {
+          "name": "tokenProgram"
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "initializeUsernameDeposit",
+      "discriminator": [
+        125,
+        255,
+        77,
+        198,
+        75,
+        226,
+        85,
+        91
+      ],
+      "accounts": [
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
         {
           "name": "deposit",
           "writable": true,
@@ -681,19 +1347,17 @@ export type TelegramPrivateTransfer = {
                 "path": "username"
               },
               {
-                "kind": "arg",
+                "kind": "account",
                 "path": "tokenMint"
               }
             ]
           }
         },
         {
-          "name": "ownerProgram",
-          "address": "97FzQdWi26mFNR21AbQNg4KqofiCLqQydQfAvRQMcXhV"
+          "name": "tokenMint"
         },
         {
-          "name": "delegationProgram",
-          "address": "DELeGGvXpWV2fqJUhqcF5ZSYMS4JTLjteaAMARRSaeSh"
+          "name": "tokenProgram"
         },
         {
           "name": "systemProgram",
@@ -704,30 +1368,25 @@ export type TelegramPrivateTransfer = {
         {
           "name": "username",
           "type": "string"
-        },
-        {
-          "name": "tokenMint",
-          "type": "pubkey"
         }
       ]
     },
     {
-      "name": "initializeDeposit",
+      "name": "migrateDeposit",
       "docs": [
-        "Initializes a deposit account for a user and token mint if it does not exist.",
+        "Grows a deposit created before the rent payer was recorded to the current layout.",
         "",
-        "Sets up a new deposit account with zero balance for the user and token mint.",
-        "If the account is already initialized, this instruction is a no-op."
+        "Permissionless. Both payers are left as `None`, so rent goes back to the owner on close."
       ],
       "discriminator": [
-        171,
-        65,
-        93,
-        225,
-        61,
-        109,
-        31,
-        227
+        122,
+        140,
+        217,
+        140,
+        223,
+        175,
+        173,
+        148
       ],
       "accounts": [
         {
@@ -772,10 +1431,6 @@ export type TelegramPrivateTransfer = {
         {
           "name": "tokenMint"
         },
-        {
-          "name": "tokenProgram",
-          "address": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
-        },
         {
           "name": "systemProgram",
           "address": "11111111111111111111111111111111"
@@ -784,16 +1439,21 @@ export type TelegramPrivateTransfer = {
       "args": []
     },
     {
-      "name": "initializeUsernameDeposit",
+      "name": "migrateUsernameDeposit",
+      "docs": [
+        "Grows a username deposit created before the rent payer was recorded to the current layout.",
+        "",
+        "Permissionless. Both payers are left as `None`, so rent goes to the verified owner on close."
+      ],
       "discriminator": [
-        125,
-        255,
+        163,
         77,
-        198,
-        75,
-        226,
-        85,
-        91
+        97,
+        102,
+        228,
+        143,
+        139,
+        160
       ],
       "accounts": [
         {
@@ -841,10 +1501,6 @@ export type TelegramPrivateTransfer = {
         {
           "name": "tokenMint"
         },
-        {
-          "name": "tokenProgram",
-          "address": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
-        },
         {
           "name": "systemProgram",
           "address": "11111111111111111111111111111111"
@@ -857,13 +1513,89 @@ export type TelegramPrivateTransfer = {
         }
       ]
     },
+    {
+      "name": "migrateVault",
+      "docs": [
+        "Grows a vault created before per-mint accounting to the current layout.",
+        "",
+        "Only the program upgrade authority can call this. What a legacy vault owes",
+        "is not tracked on-chain and its token balance says nothing about it, so the",
+        "authority attests `total_shielded`, the summed `Deposit` and `UsernameDeposit`",
+        "balances of the mint. Until migrated, the vault rejects balance changes."
+      ],
+      "discriminator": [
+        139,
+        151,
+        25,
+        211,
+        120,
+        164,
+        24,
+        215
+      ],
+      "accounts": [
+        {
+          "name": "payer",
+          "writable": true,
+          "signer": true
+        },
+        {
+          "name": "authority",
+          "signer": true
+        },
+        {
+          "name": "vault",
+          "writable": true,
+          "pda": {
+            "seeds": [
+              {
+                "kind": "const",
+                "value": [
+                  118,
+                  97,
+                  117,
+                  108,
+                  116
+                ]
+              },
+              {
+                "kind": "account",
+                "path": "tokenMint"
+              }
+            ]
+          }
+        },
+        {
+          "name": "tokenMint"
+        },
+        {
+          "name": "program",
+          "address": "97FzQdWi26mFNR21AbQNg4KqofiCLqQydQfAvRQMcXhV"
+        },
+        {
+          "name": "programData"
+        },
+        {
+          "name": "systemProgram",
+          "address": "11111111111111111111111111111111"
+        }
+      ],
+      "args": [
+        {
+          "name": "totalShielded",
+          "type": "u64"
+        }
+      ]
+    },
     {
       "name": "modifyBalance",
       "docs": [
         "Modifies the balance of a user's deposit account by transferring tokens in or out.",
         "",
         "If `args.increase` is true, tokens are transferred from the user's token account to the deposit account.",
-        "If false, tokens are transferred from the deposit account back to the user's token account."
+        "If false, tokens are transferred from the deposit account back to the user's token account.",
+        "",
+        "Deposits are credited with what the vault actually received, net of any Token-2022 transfer fee."
       ],
       "discriminator": [
         148,
@@ -954,41 +1686,8 @@ export type TelegramPrivateTransfer = {
                 "path": "user"
               },
               {
-                "kind": "const",
-                "value": [
-                  6,
-                  221,
-                  246,
-                  225,
-                  215,
-                  101,
-                  161,
-                  147,
-                  217,
-                  203,
-                  225,
-                  70,
-                  206,
-                  235,
-                  121,
-                  172,
-                  28,
-                  180,
-                  133,
-                  237,
-                  95,
-                  91,
-                  55,
-                  145,
-                  58,
-                  140,
-                  245,
-                  133,
-                  126,
-                  255,
-                  0,
-                  169
-                ]
+                "kind": "account",
+                "path": "tokenProgram"
               },
               {
                 "kind": "account",
@@ -1044,41 +1743,8 @@ export type TelegramPrivateTransfer = {
                 "path": "vault"
               },
               {
-                "kind": "const",
-                "value": [
-                  6,
-                  221,
-                  246,
-                  225,
-                  215,
-                  101,
-                  161,
-                  147,
-                  217,
-                  203,
-                  225,
-                  70,
-                  206,
-                  235,
-                  121,
-                  172,
-                  28,
-                  180,
-                  133,
-                  237,
-                  95,
-                  91,
-                  55,
-                  145,
-                  58,
-                  140,
-                  245,
-                  133,
-                  126,
-                  255,
-                  0,
-                  169
-                ]
+                "kind": "account",
+                "path": "tokenProgram"
               },
               {
                 "kind": "account",
@@ -1131,8 +1797,7 @@ export type TelegramPrivateTransfer = {
           ]
         },
         {
-          "name": "tokenProgram",
-          "address": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"
+          "name": "tokenProgram"
         },
         {
           "name": "associatedTokenProgram",
@@ -1657,6 +2322,21 @@ export type TelegramPrivateTransfer = {
       ]
     }
   ],
+  "events": [
+    {
+      "name": "vaultAudited",
+      "discriminator": [
+        216,
+        179,
+        15,
+        71,
+        22,
+        102,
+        193,
+        42
+      ]
+    }
+  ],
   "errors": [
     {
       "code": 6000,
@@ -1717,6 +2397,46 @@ export type TelegramPrivateTransfer = {
       "code": 6011,
       "name": "invalidDepositor",
       "msg": "Invalid Depositor"
+    },
+    {
+      "code": 6012,
+      "name": "invalidValidator",
+      "msg": "Invalid Validator"
+    },
+    {
+      "code": 6013,
+      "name": "unsupportedMint",
+      "msg": "Unsupported Mint"
+    },
+    {
+      "code": 6014,
+      "name": "vaultInsolvent",
+      "msg": "Vault Insolvent"
+    },
+    {
+      "code": 6015,
+      "name": "vaultAlreadyMigrated",
+      "msg": "Vault Already Migrated"
+    },
+    {
+      "code": 6016,
+      "name": "depositNotEmpty",
+      "msg": "Deposit Not Empty"
+    },
+    {
+      "code": 6017,
+      "name": "depositAlreadyMigrated",
+      "msg": "Deposit Already Migrated"
+    },
+    {
+      "code": 6018,
+      "name": "invalidRentReceiver",
+      "msg": "Invalid Rent Receiver"
+    },
+    {
+      "code": 6019,
+      "name": "invalidPermission",
+      "msg": "Invalid Permission"
     }
   ],
   "types": [
@@ -1739,6 +2459,24 @@ export type TelegramPrivateTransfer = {
           {
             "name": "amount",
             "type": "u64"
+          },
+          {
+            "name": "payer",
+            "docs": [
+              "Who paid the rent; `None` for deposits migrated from the old layout."
+            ],
+            "type": {
+              "option": "pubkey"
+            }
+          },
+          {
+            "name": "permissionPayer",
+            "docs": [
+              "Who paid for the permission; `None` if it was created before this was recorded."
+            ],
+            "type": {
+              "option": "pubkey"
+            }
           }
         ]
       }
@@ -1813,6 +2551,54 @@ export type TelegramPrivateTransfer = {
             "type": {
               "option": "u64"
             }
+          },
+          {
+            "name": "payloadHash",
+            "docs": [
+              "sha256 of `validation_bytes`, used to seed the `ConsumedInitData` PDA."
+            ],
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "telegramId",
+            "docs": [
+              "Immutable numeric Telegram user id from the `user=` payload."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "verifiedUntil",
+            "type": {
+              "option": "u64"
+            }
+          },
+          {
+            "name": "botId",
+            "docs": [
+              "Bot the initData was issued for, the `<bot_id>:WebAppData` prefix of `validation_bytes`."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "rentPayer",
+            "docs": [
+              "Paid the session's rent and gets it back on close."
+            ],
+            "type": "pubkey"
+          },
+          {
+            "name": "relayer",
+            "docs": [
+              "Relayer of the last `relayed_store`, owed its fees out of the wallet's claims."
+            ],
+            "type": {
+              "option": "pubkey"
+            }
           }
         ]
       }
@@ -1838,6 +2624,24 @@ export type TelegramPrivateTransfer = {
           {
             "name": "amount",
             "type": "u64"
+          },
+          {
+            "name": "payer",
+            "docs": [
+              "Who paid the rent; `None` for deposits migrated from the old layout."
+            ],
+            "type": {
+              "option": "pubkey"
+            }
+          },
+          {
+            "name": "permissionPayer",
+            "docs": [
+              "Who paid for the permission; `None` if it was created before this was recorded."
+            ],
+            "type": {
+              "option": "pubkey"
+            }
           }
         ]
       }
@@ -1846,7 +2650,8 @@ export type TelegramPrivateTransfer = {
       "name": "vault",
       "docs": [
         "A vault storing deposited tokens.",
-        "Has a dummy field because Anchor requires it."
+        "",
+        "`_dummy` predates the accounting and is kept so legacy vaults only need to grow."
       ],
       "type": {
         "kind": "struct",
@@ -1854,6 +2659,43 @@ export type TelegramPrivateTransfer = {
           {
             "name": "dummy",
             "type": "u8"
+          },
+          {
+            "name": "totalShielded",
+            "docs": [
+              "Sum of `Deposit.amount` and `UsernameDeposit.amount` for the vault's mint."
+            ],
+            "type": "u64"
+          }
+        ]
+      }
+    },
+    {
+      "name": "vaultAudited",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "tokenMint",
+            "type": "pubkey"
+          },
+          {
+            "name": "balance",
+            "docs": [
+              "Vault token account balance."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "liabilities",
+            "docs": [
+              "Tracked shielded supply the balance must cover."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "solvent",
+            "type": "bool"
           }
         ]
       }
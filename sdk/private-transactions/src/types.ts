@@ -161,7 +161,7 @@ export interface DelegateDepositParams {
 export interface DelegateUsernameDepositParams {
   username: string;
   tokenMint: PublicKey;
-  // session: PublicKey;
+  session?: PublicKey;
   payer: PublicKey;
   validator: PublicKey;
   rpcOptions?: RpcOptions;
@@ -13,9 +13,17 @@ import telegramPrivateTransferIdl from "../target/idl/telegram_private_transfer.
 import telegramVerificationIdl from "../target/idl/telegram_verification.json";
 import {
   createAssociatedTokenAccountIdempotent,
+  createInitializeMintInstruction,
+  createInitializePermanentDelegateInstruction,
+  createInitializeTransferFeeConfigInstruction,
   createMint,
+  createSyncNativeInstruction,
+  ExtensionType,
   getAssociatedTokenAddressSync,
+  getMintLen,
   mintToChecked,
+  NATIVE_MINT,
+  TOKEN_2022_PROGRAM_ID,
   TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { assert } from "chai";
@@ -35,6 +43,9 @@ import bs58 from "bs58";
 const DEPOSIT_PDA_SEED = Buffer.from("deposit_v2");
 const USERNAME_DEPOSIT_PDA_SEED = Buffer.from("username_deposit");
 const VAULT_PDA_SEED = Buffer.from("vault");
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
 
 const VALIDATION_BYTES: Uint8Array = new Uint8Array([
   56, 48, 54, 53, 49, 52, 48, 52, 57, 57, 58, 87, 101, 98, 65, 112, 112, 68, 97,
@@ -67,6 +78,21 @@ const VALIDATION_SIGNATURE_BYTES: Uint8Array = new Uint8Array([
 ]);
 
 const VALIDATION_USERNAME = "dig133713337";
+
+const VALIDATION_BOT_ID = 8065140499;
+
+// Rebuilds the raw `Telegram.WebApp.initData` query string behind the signed
+// data-check-string fixture, with fields out of canonical order.
+const buildRawInitData = (dataCheckString: Uint8Array): Buffer => {
+  const [, ...fields] = Buffer.from(dataCheckString)
+    .toString("utf8")
+    .split("\n");
+  const pairs = fields.reverse().map((field) => {
+    const eq = field.indexOf("=");
+    return `${field.slice(0, eq)}=${encodeURIComponent(field.slice(eq + 1))}`;
+  });
+  return Buffer.from([...pairs, "hash=0"].join("&"));
+};
 const COMMIT_POLL_MS = Number(process.env.COMMIT_POLL_MS ?? "200");
 const COMMIT_MAX_POLLS = Number(process.env.COMMIT_MAX_POLLS ?? "150");
 let lastLogMs: number | null = null;
@@ -580,6 +606,124 @@ describe("telegram-private-transfer", () => {
     assert.equal(deposit.amount.toNumber(), 0);
   });
 
+  it("Token-2022: deposits are net of transfer fees and unsafe mints are rejected", async () => {
+    const createMint2022 = async (
+      extension: ExtensionType,
+      initExtension: (mint: PublicKey) => anchor.web3.TransactionInstruction
+    ) => {
+      const mintKp = Keypair.generate();
+      const space = getMintLen([extension]);
+      const tx = new Transaction().add(
+        SystemProgram.createAccount({
+          fromPubkey: user,
+          newAccountPubkey: mintKp.publicKey,
+          space,
+          lamports:
+            await provider.connection.getMinimumBalanceForRentExemption(space),
+          programId: TOKEN_2022_PROGRAM_ID,
+        }),
+        initExtension(mintKp.publicKey),
+        createInitializeMintInstruction(
+          mintKp.publicKey,
+          6,
+          user,
+          null,
+          TOKEN_2022_PROGRAM_ID
+        )
+      );
+      await provider.sendAndConfirm(tx, [userKp, mintKp]);
+      return mintKp.publicKey;
+    };
+    const depositFor = (mint: PublicKey) =>
+      PublicKey.findProgramAddressSync(
+        [DEPOSIT_PDA_SEED, user.toBuffer(), mint.toBuffer()],
+        program.programId
+      )[0];
+
+    // 1% fee, capped well above the amount below
+    const feeMint = await createMint2022(ExtensionType.TransferFeeConfig, (mint) =>
+      createInitializeTransferFeeConfigInstruction(
+        mint,
+        user,
+        user,
+        100,
+        BigInt(1_000_000),
+        TOKEN_2022_PROGRAM_ID
+      )
+    );
+    const feeUserTokenAccount = await createAssociatedTokenAccountIdempotent(
+      provider.connection,
+      userKp,
+      feeMint,
+      user,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await mintToChecked(
+      provider.connection,
+      userKp,
+      feeMint,
+      feeUserTokenAccount,
+      user,
+      10_000,
+      6,
+      undefined,
+      undefined,
+      TOKEN_2022_PROGRAM_ID
+    );
+    await program.methods
+      .initializeDeposit()
+      .accountsPartial({
+        user,
+        deposit: depositFor(feeMint),
+        tokenMint: feeMint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .rpc();
+    await program.methods
+      .modifyBalance({ amount: new anchor.BN(10_000), increase: true })
+      .accountsPartial({
+        payer: user,
+        user,
+        vault: PublicKey.findProgramAddressSync(
+          [VAULT_PDA_SEED, feeMint.toBuffer()],
+          program.programId
+        )[0],
+        deposit: depositFor(feeMint),
+        userTokenAccount: feeUserTokenAccount,
+        tokenMint: feeMint,
+        tokenProgram: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([userKp])
+      .rpc();
+    const feeDeposit = await program.account.deposit.fetch(depositFor(feeMint));
+    assert.equal(feeDeposit.amount.toNumber(), 9_900);
+
+    const delegateMint = await createMint2022(
+      ExtensionType.PermanentDelegate,
+      (mint) =>
+        createInitializePermanentDelegateInstruction(
+          mint,
+          thirdUser,
+          TOKEN_2022_PROGRAM_ID
+        )
+    );
+    try {
+      await program.methods
+        .initializeDeposit()
+        .accountsPartial({
+          user,
+          deposit: depositFor(delegateMint),
+          tokenMint: delegateMint,
+          tokenProgram: TOKEN_2022_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("a permanent-delegate mint should be rejected");
+    } catch (err) {
+      assert.include(String(err), "UnsupportedMint");
+    }
+  });
+
   it("Modify balance", async () => {
     let tx = await awaitWithLog(
       "modifyBalance +initial/2",
@@ -675,6 +819,118 @@ describe("telegram-private-transfer", () => {
     assert.equal(deposit.amount.toNumber(), initialAmount);
   });
 
+  it("Vault tracks shielded supply and passes the solvency audit", async () => {
+    const vault = await program.account.vault.fetch(vaultPda);
+    assert.equal(vault.totalShielded.toNumber(), initialAmount);
+
+    const { events } = await program.methods
+      .auditVault()
+      .accountsPartial({
+        vault: vaultPda,
+        vaultTokenAccount,
+        tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .simulate();
+    const audited = events.find((event) => event.name === "vaultAudited");
+    assert.isTrue(audited.data.solvent);
+    assert.equal(audited.data.liabilities.toNumber(), initialAmount);
+    assert.equal(audited.data.balance.toNumber(), initialAmount);
+  });
+
+  it("Legacy vaults reject deposits until the upgrade authority migrates them", async () => {
+    // Preloaded from tests/fixtures: the 9-byte wSOL vault of the old layout.
+    const legacyVault = PublicKey.findProgramAddressSync(
+      [VAULT_PDA_SEED, NATIVE_MINT.toBuffer()],
+      program.programId
+    )[0];
+    assert.equal(
+      (await provider.connection.getAccountInfo(legacyVault)).data.length,
+      9
+    );
+
+    const amount = LAMPORTS_PER_SOL / 10;
+    const wsolAccount = await createAssociatedTokenAccountIdempotent(
+      provider.connection,
+      userKp,
+      NATIVE_MINT,
+      user
+    );
+    await provider.sendAndConfirm(
+      new Transaction().add(
+        SystemProgram.transfer({
+          fromPubkey: user,
+          toPubkey: wsolAccount,
+          lamports: amount,
+        }),
+        createSyncNativeInstruction(wsolAccount)
+      ),
+      [userKp]
+    );
+    const wsolDeposit = PublicKey.findProgramAddressSync(
+      [DEPOSIT_PDA_SEED, user.toBuffer(), NATIVE_MINT.toBuffer()],
+      program.programId
+    )[0];
+    await program.methods
+      .initializeDeposit()
+      .accountsPartial({
+        user,
+        deposit: wsolDeposit,
+        tokenMint: NATIVE_MINT,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const deposit = () =>
+      program.methods
+        .modifyBalance({ amount: new anchor.BN(amount), increase: true })
+        .accountsPartial({
+          payer: user,
+          user,
+          vault: legacyVault,
+          deposit: wsolDeposit,
+          userTokenAccount: wsolAccount,
+          tokenMint: NATIVE_MINT,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([userKp])
+        .rpc();
+    try {
+      await deposit();
+      assert.fail("a legacy vault should not take deposits");
+    } catch (err) {
+      assert.include(String(err), "AccountDidNotDeserialize");
+    }
+
+    const admin = anchor.Wallet.local().payer;
+    const migrate = (authority: Keypair) =>
+      program.methods
+        .migrateVault(new anchor.BN(0))
+        .accountsPartial({
+          payer: user,
+          authority: authority.publicKey,
+          vault: legacyVault,
+          tokenMint: NATIVE_MINT,
+          programData: PublicKey.findProgramAddressSync(
+            [program.programId.toBuffer()],
+            BPF_LOADER_UPGRADEABLE_ID
+          )[0],
+        })
+        .signers([userKp, authority])
+        .rpc();
+    try {
+      await migrate(userKp);
+      assert.fail("only the upgrade authority can migrate a vault");
+    } catch (err) {
+      assert.include(String(err), "Unauthorized");
+    }
+    await migrate(admin);
+
+    await deposit();
+    const vault = await program.account.vault.fetch(legacyVault);
+    assert.equal(vault.totalShielded.toNumber(), amount);
+  });
+
   // it("Performance: transferDeposit base", async () => {
   //   const perfTransfers = Number(process.env.PERF_TRANSFERS ?? "20");
   //   const perfAmount = new anchor.BN(Number(process.env.PERF_AMOUNT ?? "1"));
@@ -922,12 +1178,17 @@ describe("telegram-private-transfer", () => {
     );
 
     await verificationProgram.methods
-      .store(Buffer.from(VALIDATION_BYTES))
+      .store(new anchor.BN(VALIDATION_BOT_ID), buildRawInitData(VALIDATION_BYTES))
       .accounts({
         payer: otherUser,
         user: otherUser,
         // @ts-ignore
         session: sessionPda,
+        config: PublicKey.findProgramAddressSync(
+          [Buffer.from("tg_config")],
+          verificationProgram.programId
+        )[0],
+        previousBinding: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([otherUserKp])
@@ -945,8 +1206,14 @@ describe("telegram-private-transfer", () => {
     const verifyIx = await verificationProgram.methods
       .verifyTelegramInitData()
       .accounts({
+        payer: otherUser,
         session: sessionPda,
         // @ts-ignore
+        binding: PublicKey.findProgramAddressSync(
+          [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+          verificationProgram.programId
+        )[0],
+        previousSession: null,
         instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
       })
       .instruction();
@@ -1008,24 +1275,32 @@ describe("telegram-private-transfer", () => {
     );
 
     await program.methods
-      .claimUsernameDeposit(new anchor.BN(initialAmount / 10))
+      .claimUsernameDepositToWallet(new anchor.BN(initialAmount / 10))
       .accountsPartial({
-        recipientTokenAccount: otherUserTokenAccount,
+        payer: otherUser,
+        user: otherUser,
         vault: vaultPda,
+        usernameDeposit: usernameDepositPda,
+        session: sessionPda,
+        userTokenAccount: otherUserTokenAccount,
         vaultTokenAccount,
-        deposit: usernameDepositPda,
         tokenMint,
-        session: sessionPda,
         tokenProgram: TOKEN_PROGRAM_ID,
       })
+      .signers([otherUserKp])
       .rpc({ skipPreflight: true });
 
     const balanceAfter = await provider.connection.getTokenAccountBalance(
       otherUserTokenAccount
     );
-    assert.isTrue(
-      Number(balanceAfter.value.amount) > Number(balanceBefore.value.amount)
+    assert.equal(
+      Number(balanceAfter.value.amount) - Number(balanceBefore.value.amount),
+      initialAmount / 10
+    );
+    const usernameDeposit = await program.account.usernameDeposit.fetch(
+      usernameDepositPda
     );
+    assert.equal(usernameDeposit.amount.toNumber(), initialAmount / 10);
   });
 
   it("Create permission for username deposit", async () => {
@@ -1045,6 +1320,37 @@ describe("telegram-private-transfer", () => {
     await provider.connection.confirmTransaction(tx);
   });
 
+  it("Unverified payer cannot delegate a username deposit to a rogue validator", async () => {
+    const rogueValidator = Keypair.generate().publicKey;
+    // The local ephemeral validator's keypair is public, so it is no safer.
+    const localValidator = new PublicKey(
+      "mAGicPQYBMvcYveUZA5F5UNNwyHvfYh5xkLS2Fr1mev"
+    );
+    const attempts: [string, PublicKey, PublicKey | null][] = [
+      ["InvalidValidator", rogueValidator, null],
+      ["InvalidValidator", localValidator, null],
+      // someone else's session does not authorize the payer
+      ["Unauthorized", rogueValidator, sessionPda],
+    ];
+    for (const [expectedError, validator, session] of attempts) {
+      try {
+        await program.methods
+          .delegateUsernameDeposit(VALIDATION_USERNAME, tokenMint)
+          .accountsPartial({
+            payer: thirdUser,
+            deposit: usernameDepositPda,
+            validator,
+            session,
+          })
+          .signers([thirdUserKp])
+          .rpc();
+        assert.fail("delegation to a rogue validator should fail");
+      } catch (err) {
+        assert.include(String(err), expectedError);
+      }
+    }
+  });
+
   it("Delegate username deposit", async () => {
     const tx = await program.methods
       .delegateUsernameDeposit(VALIDATION_USERNAME, tokenMint)
@@ -1299,4 +1605,87 @@ describe("telegram-private-transfer", () => {
     assert.equal(baseUser?.amount.toNumber(), erUser.amount.toNumber());
     assert.equal(baseOther?.amount.toNumber(), erOther.amount.toNumber());
   });
+
+  it("Close deposits only once emptied, returning rent to the original payer", async () => {
+    try {
+      await program.methods
+        .closeDeposit()
+        .accountsPartial({
+          user,
+          rentReceiver: user,
+          deposit: depositPda,
+          permission: permissionPdaFromAccount(otherDepositPda),
+          permissionRentReceiver: user,
+          permissionProgram: PERMISSION_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("another deposit's permission should be rejected");
+    } catch (err) {
+      assert.include(String(err), "InvalidPermission");
+    }
+
+    try {
+      await program.methods
+        .closeDeposit()
+        .accountsPartial({
+          user,
+          rentReceiver: user,
+          deposit: depositPda,
+          permission: permissionPdaFromAccount(depositPda),
+          permissionRentReceiver: user,
+          permissionProgram: PERMISSION_PROGRAM_ID,
+        })
+        .rpc();
+      assert.fail("a deposit holding a balance should not close");
+    } catch (err) {
+      assert.include(String(err), "DepositNotEmpty");
+    }
+
+    // The other user's deposit was initialized (and paid for) by `user`,
+    // while its permission was paid for by the other user.
+    const otherDeposit = await program.account.deposit.fetch(otherDepositPda);
+    assert.isTrue(otherDeposit.payer.equals(user));
+    assert.isTrue(otherDeposit.permissionPayer.equals(otherUser));
+    await baseProgramOtherUser.methods
+      .modifyBalance({ amount: otherDeposit.amount, increase: false })
+      .accountsPartial({
+        user: otherUser,
+        payer: otherUser,
+        deposit: otherDepositPda,
+        userTokenAccount: otherUserTokenAccount,
+        vault: vaultPda,
+        vaultTokenAccount,
+        tokenMint,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([otherUserKp])
+      .rpc({ skipPreflight: true });
+
+    const rent = await provider.connection.getBalance(otherDepositPda);
+    const payerBefore = await provider.connection.getBalance(user);
+    const tx = await baseProgramOtherUser.methods
+      .closeDeposit()
+      .accountsPartial({
+        user: otherUser,
+        rentReceiver: user,
+        deposit: otherDepositPda,
+        permission: permissionPdaFromAccount(otherDepositPda),
+        permissionRentReceiver: otherUser,
+        permissionProgram: PERMISSION_PROGRAM_ID,
+      })
+      .signers([otherUserKp])
+      .rpc({ skipPreflight: true });
+    await provider.connection.confirmTransaction(tx);
+
+    assert.isNull(await provider.connection.getAccountInfo(otherDepositPda));
+    assert.isNull(
+      await provider.connection.getAccountInfo(
+        permissionPdaFromAccount(otherDepositPda)
+      )
+    );
+    assert.equal(
+      (await provider.connection.getBalance(user)) - payerBefore,
+      rent
+    );
+  });
 });
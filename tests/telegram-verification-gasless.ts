@@ -15,6 +15,7 @@ import { Ed25519Program } from "@solana/web3.js";
 import { publicKey } from "@coral-xyz/anchor/dist/cjs/utils";
 import { SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
 import bs58 from "bs58";
+import { createHash } from "crypto";
 
 // --- Testing fixtures ---
 const VALIDATION_BYTES: Uint8Array = new Uint8Array([
@@ -58,11 +59,37 @@ const encodeAnchorStringFilter = (value: string): string => {
 const VALIDATION_AUTH_DATE = 1763598375;
 const VALIDATION_USERNAME = "dig133713337";
 
+const VALIDATION_BOT_ID = 8065140499;
+
+// Rebuilds the raw `Telegram.WebApp.initData` query string behind the signed
+// data-check-string fixture, with fields out of canonical order.
+const buildRawInitData = (dataCheckString: Uint8Array): Buffer => {
+  const [, ...fields] = Buffer.from(dataCheckString)
+    .toString("utf8")
+    .split("\n");
+  const pairs = fields.reverse().map((field) => {
+    const eq = field.indexOf("=");
+    return `${field.slice(0, eq)}=${encodeURIComponent(field.slice(eq + 1))}`;
+  });
+  return Buffer.from([...pairs, "hash=0"].join("&"));
+};
+
 const TELEGRAM_PUBKEY_PROD_HEX =
   "e7bf03a2fa4602af4580703d88dda5bb59f32ed8b02a56c187fe7d34caed242d";
 const TELEGRAM_PUBKEY_PROD_BYTES = Buffer.from(TELEGRAM_PUBKEY_PROD_HEX, "hex");
 const TELEGRAM_PUBKEY_UINT8ARRAY = new Uint8Array(TELEGRAM_PUBKEY_PROD_BYTES);
 
+// The fixture was signed in Nov 2025, so the local config accepts old payloads.
+const TEST_MAX_AUTH_AGE_SECS = 10 * 365 * 24 * 60 * 60;
+const TEST_SESSION_TTL_SECS = 24 * 60 * 60;
+
+const RELAYER_STORE_FEE_LAMPORTS = 10_000_000;
+const RELAYER_MAX_STORES_PER_WALLET = 3;
+
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+
 describe.only("telegram-verification test suite", () => {
   const baseProvider = anchor.AnchorProvider.env();
 
@@ -93,10 +120,71 @@ describe.only("telegram-verification test suite", () => {
     .TelegramTransfer as Program<TelegramTransfer>;
 
   let sessionPda: PublicKey;
-  let vaultPda: PublicKey;
   let depositPda: PublicKey;
 
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("tg_config")],
+    verificationProgram.programId
+  );
+
+  const [relayerConfigPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("tg_relayer"), user.toBuffer()],
+    verificationProgram.programId
+  );
+  const [relayerUsagePda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("tg_relayer_usage"), user.toBuffer(), otherUser.toBuffer()],
+    verificationProgram.programId
+  );
+
   before(async () => {
+    if (!(await baseProvider.connection.getAccountInfo(configPda))) {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [verificationProgram.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+      await verificationProgram.methods
+        .initializeConfig(
+          new BN(TEST_MAX_AUTH_AGE_SECS),
+          new BN(TEST_SESSION_TTL_SECS),
+          [new BN(VALIDATION_BOT_ID)]
+        )
+        .accounts({
+          authority: baseProvider.wallet.publicKey,
+          // @ts-ignore
+          programData,
+        })
+        .rpc({ commitment: "confirmed" });
+    }
+
+    const config = await verificationProgram.account.verificationConfig.fetch(
+      configPda
+    );
+    if (!config.botIds.some((botId) => botId.eqn(VALIDATION_BOT_ID))) {
+      await verificationProgram.methods
+        .addBotId(new BN(VALIDATION_BOT_ID))
+        .accounts({
+          authority: baseProvider.wallet.publicKey,
+          // @ts-ignore
+          config: configPda,
+        })
+        .rpc({ commitment: "confirmed" });
+    }
+
+    // User A relays for user B.
+    await verificationProgram.methods
+      .setRelayer(
+        user,
+        new BN(RELAYER_STORE_FEE_LAMPORTS),
+        RELAYER_MAX_STORES_PER_WALLET
+      )
+      .accounts({
+        authority: baseProvider.wallet.publicKey,
+        // @ts-ignore
+        config: configPda,
+        relayerConfig: relayerConfigPda,
+      })
+      .rpc({ commitment: "confirmed" });
+
     const { blockhash, lastValidBlockHeight } =
       await provider.connection.getLatestBlockhash();
     const signature = await provider.connection.requestAirdrop(
@@ -114,7 +202,7 @@ describe.only("telegram-verification test suite", () => {
     );
   });
 
-  it("[gasless] Create deposit for user A and put money in the vault", async () => {
+  it("[gasless] Create deposit for user A and escrow money in it", async () => {
     await transferProgram.methods
       .depositForUsername(VALIDATION_USERNAME, new BN(initialAmount / 2))
       .accounts({
@@ -149,12 +237,19 @@ describe.only("telegram-verification test suite", () => {
     );
 
     const storeTx = await verificationProgram.methods
-      .store(Buffer.from(VALIDATION_BYTES))
+      .relayedStore(
+        new BN(VALIDATION_BOT_ID),
+        buildRawInitData(VALIDATION_BYTES)
+      )
       .accounts({
-        payer: user,
+        relayer: user,
         user: otherUser,
         // @ts-ignore
+        config: configPda,
+        relayerConfig: relayerConfigPda,
+        usage: relayerUsagePda,
         session: sessionPda,
+        previousBinding: null,
         systemProgram: SystemProgram.programId,
       })
       .transaction();
@@ -181,6 +276,15 @@ describe.only("telegram-verification test suite", () => {
       sessionPda
     );
     console.log("session:", session);
+    // The relayer paid the rent and is owed its fee out of User B's claims.
+    expect(session.rentPayer.toBase58()).to.eq(user.toBase58());
+    expect(session.relayer.toBase58()).to.eq(user.toBase58());
+
+    const usage = await verificationProgram.account.relayerUsage.fetch(
+      relayerUsagePda
+    );
+    expect(usage.stores).to.eq(1);
+    expect(usage.owedLamports.toNumber()).to.eq(RELAYER_STORE_FEE_LAMPORTS);
   });
 
   it("[gasless] User B verifies Telegram initData with native sysvar instructions", async () => {
@@ -194,8 +298,22 @@ describe.only("telegram-verification test suite", () => {
     const verifyIx = await verificationProgram.methods
       .verifyTelegramInitData()
       .accounts({
+        payer: user,
         session: sessionPda,
         // @ts-ignore
+        config: configPda,
+        consumed: PublicKey.findProgramAddressSync(
+          [
+            Buffer.from("tg_consumed"),
+            createHash("sha256").update(Buffer.from(VALIDATION_BYTES)).digest(),
+          ],
+          verificationProgram.programId
+        )[0],
+        binding: PublicKey.findProgramAddressSync(
+          [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+          verificationProgram.programId
+        )[0],
+        previousSession: null,
         instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
       })
       .instruction();
@@ -226,25 +344,74 @@ describe.only("telegram-verification test suite", () => {
     expect(session.verifiedAt).to.not.be.null;
   });
 
+  it("User B can't close the relayed session while it owes the relayer", async () => {
+    let threw = false;
+    try {
+      await verificationProgram.methods
+        .closeSession()
+        .accounts({
+          user: otherUser,
+          // @ts-ignore
+          session: sessionPda,
+          rentPayer: user,
+          binding: PublicKey.findProgramAddressSync(
+            [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+            verificationProgram.programId
+          )[0],
+          relayerUsage: relayerUsagePda,
+        })
+        .signers([otherUserKp])
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      threw = true;
+      expect(String(e)).to.contain("RelayerFeeOutstanding");
+    }
+    expect(threw).to.eq(true);
+  });
+
   it("User B claims deposit from user A with verified initData", async () => {
-    [vaultPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("vault")],
-      transferProgram.programId
-    );
-    let vault = await transferProgram.account.vault.fetch(vaultPda);
-    const vaultBalanceInitial = vault.totalDeposited.toNumber();
+    const claimAmount = initialAmount / 4;
+
+    // A relayed session can't be claimed from without settling the relayer.
+    let threw = false;
+    try {
+      await transferProgram.methods
+        .claimDeposit(new BN(claimAmount))
+        .accounts({
+          recipient: otherUser,
+          // @ts-ignore
+          deposit: depositPda,
+          session: sessionPda,
+          relayerUsage: null,
+          relayer: null,
+          verificationProgram: null,
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      threw = true;
+      expect(String(e)).to.contain("MissingRelayerUsage");
+    }
+    expect(threw).to.eq(true);
 
+    // The claim pays back the relayer out of the claimed amount.
     const claimTx = await transferProgram.methods
-      .claimDeposit(new BN(initialAmount / 4))
+      .claimDeposit(new BN(claimAmount))
       .accounts({
         recipient: otherUser,
         // @ts-ignore
-        vault: vaultPda,
         deposit: depositPda,
         session: sessionPda,
+        relayerUsage: relayerUsagePda,
+        relayer: user,
+        verificationProgram: verificationProgram.programId,
       })
       .transaction();
 
+    const relayerBalanceBefore = await provider.connection.getBalance(user);
+    const otherUserBalanceBefore = await provider.connection.getBalance(
+      otherUser
+    );
+
     claimTx.feePayer = user;
     const { blockhash, lastValidBlockHeight } =
       await provider.connection.getLatestBlockhash();
@@ -260,9 +427,20 @@ describe.only("telegram-verification test suite", () => {
     );
     await provider.connection.confirmTransaction(sig, "confirmed");
 
-    const deposit = await transferProgram.account.deposit.fetch(depositPda);
-
     const otherUserBalance = await provider.connection.getBalance(otherUser);
-    console.log("otherUserBalance:", otherUserBalance);
+    expect(otherUserBalance - otherUserBalanceBefore).to.eq(
+      claimAmount - RELAYER_STORE_FEE_LAMPORTS
+    );
+
+    const usage = await verificationProgram.account.relayerUsage.fetch(
+      relayerUsagePda
+    );
+    expect(usage.owedLamports.toNumber()).to.eq(0);
+    expect(usage.reimbursedLamports.toNumber()).to.eq(
+      RELAYER_STORE_FEE_LAMPORTS
+    );
+    const relayerBalanceAfter = await provider.connection.getBalance(user);
+    expect(relayerBalanceAfter).to.be.greaterThan(relayerBalanceBefore);
   });
+
 });
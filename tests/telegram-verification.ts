@@ -15,6 +15,7 @@ import { Ed25519Program } from "@solana/web3.js";
 import { publicKey } from "@coral-xyz/anchor/dist/cjs/utils";
 import { SYSVAR_INSTRUCTIONS_PUBKEY } from "@solana/web3.js";
 import bs58 from "bs58";
+import { createHash, createPrivateKey, sign } from "crypto";
 
 // --- Testing fixtures ---
 const VALIDATION_BYTES: Uint8Array = new Uint8Array([
@@ -58,11 +59,101 @@ const encodeAnchorStringFilter = (value: string): string => {
 const VALIDATION_AUTH_DATE = 1763598375;
 const VALIDATION_USERNAME = "dig133713337";
 
+const VALIDATION_BOT_ID = 8065140499;
+
+// Rebuilds the raw `Telegram.WebApp.initData` query string behind the signed
+// data-check-string fixture, with fields out of canonical order.
+const buildRawInitData = (dataCheckString: Uint8Array): Buffer => {
+  const [, ...fields] = Buffer.from(dataCheckString)
+    .toString("utf8")
+    .split("\n");
+  const pairs = fields.reverse().map((field) => {
+    const eq = field.indexOf("=");
+    return `${field.slice(0, eq)}=${encodeURIComponent(field.slice(eq + 1))}`;
+  });
+  return Buffer.from([...pairs, "hash=0"].join("&"));
+};
+const VALIDATION_TELEGRAM_ID = 8138797767;
+
 const TELEGRAM_PUBKEY_PROD_HEX =
   "e7bf03a2fa4602af4580703d88dda5bb59f32ed8b02a56c187fe7d34caed242d";
 const TELEGRAM_PUBKEY_PROD_BYTES = Buffer.from(TELEGRAM_PUBKEY_PROD_HEX, "hex");
 const TELEGRAM_PUBKEY_UINT8ARRAY = new Uint8Array(TELEGRAM_PUBKEY_PROD_BYTES);
 
+// The fixture was signed in Nov 2025, so the local config accepts old payloads.
+const TEST_MAX_AUTH_AGE_SECS = 10 * 365 * 24 * 60 * 60;
+const TEST_SESSION_TTL_SECS = 24 * 60 * 60;
+
+const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+  "BPFLoaderUpgradeab1e11111111111111111111111"
+);
+
+const findConsumedPda = (programId: PublicKey, payload: Uint8Array) =>
+  PublicKey.findProgramAddressSync(
+    [
+      Buffer.from("tg_consumed"),
+      createHash("sha256").update(Buffer.from(payload)).digest(),
+    ],
+    programId
+  )[0];
+
+const WALLET_ATTESTATION_PREFIX = Buffer.from("telegram-verification:wallet:");
+
+const signEd25519 = (signer: Keypair, message: Uint8Array): Buffer =>
+  sign(
+    null,
+    Buffer.from(message),
+    createPrivateKey({
+      key: Buffer.concat([
+        Buffer.from("302e020100300506032b657004220420", "hex"),
+        Buffer.from(signer.secretKey.slice(0, 32)),
+      ]),
+      format: "der",
+      type: "pkcs8",
+    })
+  );
+
+// Ed25519 precompile instruction checking several signatures at once.
+const createMultiSigEd25519Instruction = (
+  entries: {
+    publicKey: Uint8Array;
+    message: Uint8Array;
+    signature: Uint8Array;
+  }[]
+): web3.TransactionInstruction => {
+  const headerLen = 2 + entries.length * 14;
+  const header = Buffer.alloc(headerLen);
+  header.writeUInt8(entries.length, 0);
+
+  const payloads: Buffer[] = [];
+  let offset = headerLen;
+  entries.forEach(({ publicKey, message, signature }, i) => {
+    const base = 2 + i * 14;
+    const publicKeyOffset = offset;
+    const signatureOffset = publicKeyOffset + 32;
+    const messageOffset = signatureOffset + 64;
+    header.writeUInt16LE(signatureOffset, base);
+    header.writeUInt16LE(0xffff, base + 2);
+    header.writeUInt16LE(publicKeyOffset, base + 4);
+    header.writeUInt16LE(0xffff, base + 6);
+    header.writeUInt16LE(messageOffset, base + 8);
+    header.writeUInt16LE(message.length, base + 10);
+    header.writeUInt16LE(0xffff, base + 12);
+    payloads.push(
+      Buffer.from(publicKey),
+      Buffer.from(signature),
+      Buffer.from(message)
+    );
+    offset = messageOffset + message.length;
+  });
+
+  return new web3.TransactionInstruction({
+    keys: [],
+    programId: Ed25519Program.programId,
+    data: Buffer.concat([header, ...payloads]),
+  });
+};
+
 describe.only("telegram-verification test suite", () => {
   const baseProvider = anchor.AnchorProvider.env();
 
@@ -97,10 +188,47 @@ describe.only("telegram-verification test suite", () => {
     .TelegramTransfer as Program<TelegramTransfer>;
 
   let sessionPda: PublicKey;
-  let vaultPda: PublicKey;
   let depositPda: PublicKey;
 
+  const [configPda] = PublicKey.findProgramAddressSync(
+    [Buffer.from("tg_config")],
+    verificationProgram.programId
+  );
+
   before(async () => {
+    if (!(await baseProvider.connection.getAccountInfo(configPda))) {
+      const [programData] = PublicKey.findProgramAddressSync(
+        [verificationProgram.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+      );
+      await verificationProgram.methods
+        .initializeConfig(
+          new BN(TEST_MAX_AUTH_AGE_SECS),
+          new BN(TEST_SESSION_TTL_SECS),
+          [new BN(VALIDATION_BOT_ID)]
+        )
+        .accounts({
+          authority: baseProvider.wallet.publicKey,
+          // @ts-ignore
+          programData,
+        })
+        .rpc({ commitment: "confirmed" });
+    }
+
+    const config = await verificationProgram.account.verificationConfig.fetch(
+      configPda
+    );
+    if (!config.botIds.some((botId) => botId.eqn(VALIDATION_BOT_ID))) {
+      await verificationProgram.methods
+        .addBotId(new BN(VALIDATION_BOT_ID))
+        .accounts({
+          authority: baseProvider.wallet.publicKey,
+          // @ts-ignore
+          config: configPda,
+        })
+        .rpc({ commitment: "confirmed" });
+    }
+
     const { blockhash, lastValidBlockHeight } =
       await provider.connection.getLatestBlockhash();
     const signature = await provider.connection.requestAirdrop(
@@ -144,7 +272,65 @@ describe.only("telegram-verification test suite", () => {
     );
   });
 
-  it("Create deposit for user A and put money in the vault", async () => {
+  it("Config authority manages the Telegram key registry", async () => {
+    const testEnvKey = Array.from(
+      Buffer.from(
+        "40055058a4ee38156a06562e52eece92a771bcd8346a8c4615cb7376eddf72ec",
+        "hex"
+      )
+    );
+
+    let threw = false;
+    try {
+      await verificationProgram.methods
+        .addTelegramKey(testEnvKey, new BN(0), null)
+        .accounts({
+          authority: user,
+          // @ts-ignore
+          config: configPda,
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      threw = true;
+    }
+    expect(threw).to.be.true;
+
+    await verificationProgram.methods
+      .addTelegramKey(testEnvKey, new BN(0), null)
+      .accounts({
+        authority: baseProvider.wallet.publicKey,
+        // @ts-ignore
+        config: configPda,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    await verificationProgram.methods
+      .retireTelegramKey(testEnvKey)
+      .accounts({
+        authority: baseProvider.wallet.publicKey,
+        // @ts-ignore
+        config: configPda,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const config = await verificationProgram.account.verificationConfig.fetch(
+      configPda
+    );
+    const prodKey = config.telegramKeys.find(
+      (key) =>
+        Buffer.from(key.pubkey).toString("hex") === TELEGRAM_PUBKEY_PROD_HEX
+    );
+    expect(prodKey).to.not.be.undefined;
+    expect(prodKey.expiresAt).to.eq(null);
+
+    const retired = config.telegramKeys.find(
+      (key) => Buffer.from(key.pubkey).equals(Buffer.from(testEnvKey))
+    );
+    expect(retired.expiresAt).to.not.be.null;
+  });
+
+  it("Create deposit for user A and escrow money in it", async () => {
     await transferProgram.methods
       .depositForUsername(VALIDATION_USERNAME, new BN(initialAmount / 2))
       .accounts({
@@ -191,12 +377,7 @@ describe.only("telegram-verification test suite", () => {
   });
 
   it("Top up existing deposit for user A", async () => {
-    [vaultPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from("vault")],
-      transferProgram.programId
-    );
-    let vault = await transferProgram.account.vault.fetch(vaultPda);
-    let totalDeposited = vault.totalDeposited.toNumber();
+    const escrowedBefore = await provider.connection.getBalance(depositPda);
 
     await transferProgram.methods
       .depositForUsername(VALIDATION_USERNAME, new BN(initialAmount / 4))
@@ -212,9 +393,9 @@ describe.only("telegram-verification test suite", () => {
     expect(deposit.user.toBase58()).to.equal(user.toBase58());
     expect(deposit.username).to.equal(VALIDATION_USERNAME);
 
-    vault = await transferProgram.account.vault.fetch(vaultPda);
-    expect(vault.totalDeposited.toNumber()).to.equal(
-      totalDeposited + initialAmount / 4
+    expect(deposit.escrowed).to.be.true;
+    expect(await provider.connection.getBalance(depositPda)).to.equal(
+      escrowedBefore + initialAmount / 4
     );
   });
 
@@ -224,7 +405,6 @@ describe.only("telegram-verification test suite", () => {
       .accounts({
         depositor: user,
         // @ts-ignore
-        vault: vaultPda,
         deposit: depositPda,
       })
       .signers([userKp])
@@ -244,7 +424,6 @@ describe.only("telegram-verification test suite", () => {
         .accounts({
           depositor: otherUser,
           // @ts-ignore
-          vault: vaultPda,
           deposit: depositPda,
         })
         .rpc({ commitment: "confirmed" });
@@ -254,6 +433,339 @@ describe.only("telegram-verification test suite", () => {
     expect(threw).to.be.true;
   });
 
+  const itemPda = (nonce: number) =>
+    PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("deposit_item"),
+        user.toBuffer(),
+        Buffer.from(VALIDATION_USERNAME),
+        new BN(nonce).toArrayLike(Buffer, "le", 8),
+      ],
+      transferProgram.programId
+    )[0];
+
+  it("Itemized deposits keep one account per nonce", async () => {
+    for (const nonce of [0, 1]) {
+      await transferProgram.methods
+        .depositItemForUsername(
+          VALIDATION_USERNAME,
+          new BN(nonce),
+          new BN(initialAmount / 8),
+          `gift #${nonce + 1}`,
+          "Alice"
+        )
+        .accounts({
+          payer: user,
+          depositor: user,
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+    }
+
+    const first = await transferProgram.account.deposit.fetch(itemPda(0));
+    const second = await transferProgram.account.deposit.fetch(itemPda(1));
+    expect(first.nonce.toNumber()).to.equal(0);
+    expect(first.memo).to.equal("gift #1");
+    expect(second.memo).to.equal("gift #2");
+    expect(second.senderName).to.equal("Alice");
+    expect(second.amount.toNumber()).to.equal(initialAmount / 8);
+    expect(second.createdAt.toNumber()).to.be.greaterThan(0);
+
+    // The running deposit counts the items, so a nonce can't be skipped.
+    const [runningPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("deposit"), user.toBuffer(), Buffer.from(VALIDATION_USERNAME)],
+      transferProgram.programId
+    );
+    const running = await transferProgram.account.deposit.fetch(runningPda);
+    expect(running.lastNonce.toNumber()).to.equal(2);
+    let threw = false;
+    try {
+      await transferProgram.methods
+        .depositItemForUsername(
+          VALIDATION_USERNAME,
+          new BN(5),
+          new BN(initialAmount / 8),
+          "",
+          ""
+        )
+        .accounts({
+          payer: user,
+          depositor: user,
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      threw = true;
+      expect(String(e)).to.contain("InvalidNonce");
+    }
+    expect(threw).to.be.true;
+
+    await transferProgram.methods
+      .refundDeposit(new BN(initialAmount / 8))
+      .accounts({
+        depositor: user,
+        // @ts-ignore
+        deposit: itemPda(1),
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    const refunded = await transferProgram.account.deposit.fetch(itemPda(1));
+    expect(refunded.amount.toNumber()).to.equal(0);
+    const untouched = await transferProgram.account.deposit.fetch(itemPda(0));
+    expect(untouched.amount.toNumber()).to.equal(initialAmount / 8);
+  });
+
+  it("Migrates a legacy 92-byte deposit out of the shared vault", async () => {
+    // Preloaded from tests/fixtures: 1_000_000 lamports booked for "legacy_user".
+    const legacyDepositPda = new PublicKey(
+      "EWvpTFJgEDYj83rfqZmRWAsqrHJPqd1BYkDErPrzrUPD"
+    );
+    const legacyAmount = 1_000_000;
+    expect(
+      (await provider.connection.getAccountInfo(legacyDepositPda)).data.length
+    ).to.equal(92);
+
+    await transferProgram.methods
+      .migrateDeposit()
+      .accounts({
+        payer: user,
+        // @ts-ignore
+        deposit: legacyDepositPda,
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    const migrated = await transferProgram.account.deposit.fetch(
+      legacyDepositPda
+    );
+    expect(migrated.username).to.equal("legacy_user");
+    expect(migrated.amount.toNumber()).to.equal(legacyAmount);
+    expect(migrated.escrowed).to.be.true;
+    expect(migrated.unlockAt).to.equal(null);
+    const [, bump] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("deposit"),
+        migrated.user.toBuffer(),
+        Buffer.from("legacy_user"),
+      ],
+      transferProgram.programId
+    );
+    expect(migrated.bump).to.equal(bump);
+
+    const account = await provider.connection.getAccountInfo(legacyDepositPda);
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(
+      account.data.length
+    );
+    expect(account.lamports).to.equal(rent + legacyAmount);
+
+    let threw = false;
+    try {
+      await transferProgram.methods
+        .migrateDeposit()
+        .accounts({
+          payer: user,
+          // @ts-ignore
+          deposit: legacyDepositPda,
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      threw = true;
+      expect(String(e)).to.contain("DepositAlreadyMigrated");
+    }
+    expect(threw).to.be.true;
+  });
+
+  it("Depositor cancels the unvested remainder of a scheduled deposit", async () => {
+    await transferProgram.methods
+      .depositItemForUsername(
+        VALIDATION_USERNAME,
+        new BN(2),
+        new BN(initialAmount / 8),
+        "payroll",
+        "Alice"
+      )
+      .accounts({
+        payer: user,
+        depositor: user,
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    const unlockAt = Math.floor(Date.now() / 1000) + 3600;
+    await transferProgram.methods
+      .setDepositSchedule(new BN(unlockAt), new BN(unlockAt + 3600))
+      .accounts({
+        depositor: user,
+        // @ts-ignore
+        deposit: itemPda(2),
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    const scheduled = await transferProgram.account.deposit.fetch(itemPda(2));
+    expect(scheduled.unlockAt.toNumber()).to.equal(unlockAt);
+    expect(scheduled.vestingEnd.toNumber()).to.equal(unlockAt + 3600);
+
+    // A plain refund could take back vested funds, so it is refused.
+    try {
+      await transferProgram.methods
+        .refundDeposit(new BN(1))
+        .accounts({
+          depositor: user,
+          // @ts-ignore
+          deposit: itemPda(2),
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("refund of a vesting deposit should fail");
+    } catch (e) {
+      expect(String(e)).to.include("DepositVesting");
+    }
+
+    // Nothing has unlocked yet, so cancelling returns the whole balance.
+    await transferProgram.methods
+      .cancelVesting()
+      .accounts({
+        depositor: user,
+        // @ts-ignore
+        deposit: itemPda(2),
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    const cancelled = await transferProgram.account.deposit.fetch(itemPda(2));
+    expect(cancelled.amount.toNumber()).to.equal(0);
+    expect(cancelled.unlockAt).to.eq(null);
+  });
+
+  it("Depositor cannot refund what vested before cancelling", async () => {
+    await transferProgram.methods
+      .depositItemForUsername(
+        VALIDATION_USERNAME,
+        new BN(3),
+        new BN(initialAmount / 8),
+        "payroll",
+        "Alice"
+      )
+      .accounts({
+        payer: user,
+        depositor: user,
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    // Halfway through the schedule, about half of the deposit has vested.
+    const now = Math.floor(Date.now() / 1000);
+    await transferProgram.methods
+      .setDepositSchedule(new BN(now - 3600), new BN(now + 3600))
+      .accounts({
+        depositor: user,
+        // @ts-ignore
+        deposit: itemPda(3),
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    await transferProgram.methods
+      .cancelVesting()
+      .accounts({
+        depositor: user,
+        // @ts-ignore
+        deposit: itemPda(3),
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    const cancelled = await transferProgram.account.deposit.fetch(itemPda(3));
+    expect(cancelled.amount.toNumber()).to.be.greaterThan(0);
+    expect(cancelled.vestedLocked.toNumber()).to.equal(
+      cancelled.amount.toNumber()
+    );
+
+    try {
+      await transferProgram.methods
+        .refundDeposit(new BN(1))
+        .accounts({
+          depositor: user,
+          // @ts-ignore
+          deposit: itemPda(3),
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("refund of vested funds should fail");
+    } catch (e) {
+      expect(String(e)).to.include("DepositLocked");
+    }
+
+    // An expiry would hand the vested funds to the refund crank.
+    try {
+      await transferProgram.methods
+        .setDepositExpiry(new BN(now + 60))
+        .accounts({
+          depositor: user,
+          // @ts-ignore
+          deposit: itemPda(3),
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("expiry on vested funds should fail");
+    } catch (e) {
+      expect(String(e)).to.include("DepositLocked");
+    }
+  });
+
+  it("Depositor cannot top up an expired deposit", async () => {
+    const username = "expiring_user";
+    const [expiringPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("deposit"), user.toBuffer(), Buffer.from(username)],
+      transferProgram.programId
+    );
+    await transferProgram.methods
+      .depositForUsername(username, new BN(initialAmount / 8))
+      .accounts({
+        payer: user,
+        depositor: user,
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    const expiresAt = Math.floor(Date.now() / 1000) + 2;
+    await transferProgram.methods
+      .setDepositExpiry(new BN(expiresAt))
+      .accounts({
+        depositor: user,
+        // @ts-ignore
+        deposit: expiringPda,
+      })
+      .signers([userKp])
+      .rpc({ commitment: "confirmed" });
+
+    // wait for the cluster clock, not the local one, to pass the expiry
+    for (;;) {
+      const slot = await provider.connection.getSlot("confirmed");
+      if ((await provider.connection.getBlockTime(slot)) > expiresAt) break;
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+
+    try {
+      await transferProgram.methods
+        .depositForUsername(username, new BN(initialAmount / 8))
+        .accounts({
+          payer: user,
+          depositor: user,
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("top-up of an expired deposit should fail");
+    } catch (e) {
+      expect(String(e)).to.include("DepositExpired");
+    }
+    const deposit = await transferProgram.account.deposit.fetch(expiringPda);
+    expect(deposit.amount.toNumber()).to.equal(initialAmount / 8);
+  });
+
   it("User B stores initData in new TelegramSession PDA", async () => {
     [sessionPda] = PublicKey.findProgramAddressSync(
       [Buffer.from("tg_session"), otherUser.toBuffer()],
@@ -261,12 +773,14 @@ describe.only("telegram-verification test suite", () => {
     );
 
     await verificationProgram.methods
-      .store(Buffer.from(VALIDATION_BYTES))
+      .store(new BN(VALIDATION_BOT_ID), buildRawInitData(VALIDATION_BYTES))
       .accounts({
         payer: otherUser,
         user: otherUser,
         // @ts-ignore
         session: sessionPda,
+        config: configPda,
+        previousBinding: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([otherUserKp])
@@ -281,6 +795,7 @@ describe.only("telegram-verification test suite", () => {
     expect(session.verified).to.eq(false);
     expect(session.verifiedAt).to.eq(null);
     expect(session.authAt).to.not.be.null;
+    expect(session.telegramId.toNumber()).to.eq(VALIDATION_TELEGRAM_ID);
   });
 
   it("User B stores initData in existing TelegramSession PDA", async () => {
@@ -290,12 +805,14 @@ describe.only("telegram-verification test suite", () => {
     );
 
     await verificationProgram.methods
-      .store(Buffer.from(VALIDATION_BYTES))
+      .store(new BN(VALIDATION_BOT_ID), buildRawInitData(VALIDATION_BYTES))
       .accounts({
         payer: otherUser,
         user: otherUser,
         // @ts-ignore
         session: sessionPda,
+        config: configPda,
+        previousBinding: null,
         systemProgram: SystemProgram.programId,
       })
       .signers([otherUserKp])
@@ -323,8 +840,19 @@ describe.only("telegram-verification test suite", () => {
     const verifyIx = await verificationProgram.methods
       .verifyTelegramInitData()
       .accounts({
+        payer: otherUser,
         session: sessionPda,
         // @ts-ignore
+        config: configPda,
+        consumed: findConsumedPda(
+          verificationProgram.programId,
+          VALIDATION_BYTES
+        ),
+        binding: PublicKey.findProgramAddressSync(
+          [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+          verificationProgram.programId
+        )[0],
+        previousSession: null,
         instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
       })
       .instruction();
@@ -353,20 +881,215 @@ describe.only("telegram-verification test suite", () => {
     expect(session.username).to.eq(VALIDATION_USERNAME);
     expect(session.verified).to.be.true;
     expect(session.verifiedAt).to.not.be.null;
+
+    const [bindingPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+      verificationProgram.programId
+    );
+    const binding = await verificationProgram.account.usernameBinding.fetch(
+      bindingPda
+    );
+    expect(binding.username).to.eq(VALIDATION_USERNAME);
+    expect(binding.userWallet.toBase58()).to.eq(otherUser.toBase58());
+    expect(binding.session.toBase58()).to.eq(sessionPda.toBase58());
+  });
+
+  it("User C cannot replay User B's consumed initData", async () => {
+    const [thirdSessionPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tg_session"), thirdUser.toBuffer()],
+      verificationProgram.programId
+    );
+
+    await verificationProgram.methods
+      .store(new BN(VALIDATION_BOT_ID), buildRawInitData(VALIDATION_BYTES))
+      .accounts({
+        payer: thirdUser,
+        user: thirdUser,
+        // @ts-ignore
+        session: thirdSessionPda,
+        config: configPda,
+        previousBinding: null,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([thirduserKp])
+      .rpc({ commitment: "confirmed" });
+
+    const ed25519Ix = Ed25519Program.createInstructionWithPublicKey({
+      publicKey: TELEGRAM_PUBKEY_UINT8ARRAY,
+      message: VALIDATION_BYTES,
+      signature: VALIDATION_SIGNATURE_BYTES,
+    });
+
+    const verifyIx = await verificationProgram.methods
+      .verifyTelegramInitData()
+      .accounts({
+        payer: thirdUser,
+        session: thirdSessionPda,
+        // @ts-ignore
+        config: configPda,
+        consumed: findConsumedPda(
+          verificationProgram.programId,
+          VALIDATION_BYTES
+        ),
+        binding: PublicKey.findProgramAddressSync(
+          [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+          verificationProgram.programId
+        )[0],
+        previousSession: null,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+      })
+      .instruction();
+
+    const tx = new Transaction().add(ed25519Ix, verifyIx);
+    tx.feePayer = thirdUser;
+    const { blockhash } = await provider.connection.getLatestBlockhash();
+    tx.recentBlockhash = blockhash;
+    tx.sign(thirduserKp);
+
+    let threw = false;
+    try {
+      const sig = await provider.connection.sendRawTransaction(tx.serialize(), {
+        skipPreflight: false,
+      });
+      await provider.connection.confirmTransaction(sig, "confirmed");
+    } catch (e) {
+      threw = true;
+      expect(String(e)).to.contain("Replay");
+    }
+    expect(threw).to.eq(true);
+
+    const session = await verificationProgram.account.telegramSession.fetch(
+      thirdSessionPda
+    );
+    expect(session.verified).to.eq(false);
+  });
+
+  it("Consumed initData cannot be closed while it is still fresh", async () => {
+    const payloadHash = createHash("sha256")
+      .update(Buffer.from(VALIDATION_BYTES))
+      .digest();
+
+    let threw = false;
+    try {
+      await verificationProgram.methods
+        .closeConsumedInitData(Array.from(payloadHash))
+        .accounts({
+          payer: otherUser,
+          // @ts-ignore
+          config: configPda,
+          consumed: findConsumedPda(
+            verificationProgram.programId,
+            VALIDATION_BYTES
+          ),
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      threw = true;
+      expect(String(e)).to.contain("ConsumedInitDataStillFresh");
+    }
+    expect(threw).to.eq(true);
+  });
+
+  it("Wallet attestation is required once enabled and accepted from a multi-signature precompile", async () => {
+    const [thirdSessionPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tg_session"), thirdUser.toBuffer()],
+      verificationProgram.programId
+    );
+    const setRequireWalletAttestation = (enabled: boolean) =>
+      verificationProgram.methods
+        .setRequireWalletAttestation(enabled)
+        .accounts({
+          authority: baseProvider.wallet.publicKey,
+          // @ts-ignore
+          config: configPda,
+        })
+        .rpc({ commitment: "confirmed" });
+
+    const sendVerify = async (ed25519Ix: web3.TransactionInstruction) => {
+      const verifyIx = await verificationProgram.methods
+        .verifyTelegramInitData()
+        .accounts({
+          payer: thirdUser,
+          session: thirdSessionPda,
+          // @ts-ignore
+          config: configPda,
+          consumed: findConsumedPda(
+            verificationProgram.programId,
+            VALIDATION_BYTES
+          ),
+          binding: PublicKey.findProgramAddressSync(
+            [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+            verificationProgram.programId
+          )[0],
+          previousSession: null,
+          instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        })
+        .instruction();
+
+      const tx = new Transaction().add(ed25519Ix, verifyIx);
+      tx.feePayer = thirdUser;
+      const { blockhash } = await provider.connection.getLatestBlockhash();
+      tx.recentBlockhash = blockhash;
+      tx.sign(thirduserKp);
+      try {
+        const sig = await provider.connection.sendRawTransaction(
+          tx.serialize(),
+          { skipPreflight: false }
+        );
+        await provider.connection.confirmTransaction(sig, "confirmed");
+        return "";
+      } catch (e) {
+        return String(e);
+      }
+    };
+
+    const telegramEntry = {
+      publicKey: TELEGRAM_PUBKEY_UINT8ARRAY,
+      message: VALIDATION_BYTES,
+      signature: VALIDATION_SIGNATURE_BYTES,
+    };
+
+    await setRequireWalletAttestation(true);
+    try {
+      const missing = await sendVerify(
+        Ed25519Program.createInstructionWithPublicKey(telegramEntry)
+      );
+      expect(missing).to.contain("MissingWalletAttestation");
+
+      // Both signatures pass; the payload itself was already consumed by user B.
+      const attestation = Buffer.concat([
+        WALLET_ATTESTATION_PREFIX,
+        createHash("sha256").update(Buffer.from(VALIDATION_BYTES)).digest(),
+      ]);
+      const attested = await sendVerify(
+        createMultiSigEd25519Instruction([
+          {
+            publicKey: thirdUser.toBytes(),
+            message: attestation,
+            signature: signEd25519(thirduserKp, attestation),
+          },
+          telegramEntry,
+        ])
+      );
+      expect(attested).to.contain("Replay");
+    } finally {
+      await setRequireWalletAttestation(false);
+    }
   });
 
   it("User B claims deposit from user A with verified initData", async () => {
-    let vault = await transferProgram.account.vault.fetch(vaultPda);
-    const vaultBalanceInitial = vault.totalDeposited.toNumber();
+    const escrowedBefore = await provider.connection.getBalance(depositPda);
 
     await transferProgram.methods
       .claimDeposit(new BN(initialAmount / 4))
       .accounts({
         recipient: otherUser,
         // @ts-ignore
-        vault: vaultPda,
         deposit: depositPda,
         session: sessionPda,
+        relayerUsage: null,
+        relayer: null,
+        verificationProgram: null,
       })
       .rpc({ commitment: "confirmed" });
 
@@ -378,9 +1101,185 @@ describe.only("telegram-verification test suite", () => {
     const otherUserBalance = await provider.connection.getBalance(otherUser);
     expect(otherUserBalance).to.greaterThan(initialAmount);
 
-    vault = await transferProgram.account.vault.fetch(vaultPda);
-    expect(vault.totalDeposited.toNumber()).to.equal(
-      vaultBalanceInitial - initialAmount / 4
+    expect(await provider.connection.getBalance(depositPda)).to.equal(
+      escrowedBefore - initialAmount / 4
+    );
+  });
+
+  it("User B claims part of the deposit to a separate destination wallet", async () => {
+    const destination = Keypair.generate().publicKey;
+
+    // The recipient check is on the signing wallet, not on the destination.
+    await transferProgram.methods
+      .claimDepositTo(new BN(initialAmount / 8))
+      .accounts({
+        userWallet: otherUser,
+        destination,
+        // @ts-ignore
+        deposit: depositPda,
+        session: sessionPda,
+        relayerUsage: null,
+        relayer: null,
+        verificationProgram: null,
+      })
+      .signers([otherUserKp])
+      .rpc({ commitment: "confirmed" });
+
+    const deposit = await transferProgram.account.deposit.fetch(depositPda);
+    expect(deposit.amount.toNumber()).to.equal(initialAmount / 8);
+    expect(await provider.connection.getBalance(destination)).to.equal(
+      initialAmount / 8
+    );
+
+    try {
+      await transferProgram.methods
+        .claimDepositTo(new BN(1))
+        .accounts({
+          userWallet: user,
+          destination,
+          // @ts-ignore
+          deposit: depositPda,
+          session: sessionPda,
+          relayerUsage: null,
+          relayer: null,
+          verificationProgram: null,
+        })
+        .signers([userKp])
+        .rpc({ commitment: "confirmed" });
+      expect.fail("claim by a wallet other than the session's should fail");
+    } catch (e) {
+      expect(String(e)).to.include("InvalidRecipient");
+    }
+  });
+
+  it("User B claims every remaining deposit in one instruction", async () => {
+    const balanceBefore = await provider.connection.getBalance(otherUser);
+
+    const sig = await transferProgram.methods
+      .claimMany(null)
+      .accounts({
+        recipient: otherUser,
+        session: sessionPda,
+        relayerUsage: null,
+        relayer: null,
+        verificationProgram: null,
+      })
+      .remainingAccounts(
+        [depositPda, itemPda(0), itemPda(1)].map((pubkey) => ({
+          pubkey,
+          isSigner: false,
+          isWritable: true,
+        }))
+      )
+      .rpc({ commitment: "confirmed" });
+
+    for (const pda of [depositPda, itemPda(0)]) {
+      const deposit = await transferProgram.account.deposit.fetch(pda);
+      expect(deposit.amount.toNumber()).to.equal(0);
+    }
+    expect(await provider.connection.getBalance(otherUser)).to.equal(
+      balanceBefore + initialAmount / 8 + initialAmount / 8
+    );
+
+    // One event per paid-out deposit; the already refunded item is skipped.
+    const tx = await provider.connection.getTransaction(sig, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(
+      transferProgram.programId,
+      new anchor.BorshCoder(transferProgram.idl)
     );
+    const claimed = [...parser.parseLogs(tx.meta.logMessages)].filter(
+      (event) => event.name === "depositClaimed"
+    );
+    expect(claimed.map((event) => event.data.deposit.toBase58())).to.deep.eq([
+      depositPda.toBase58(),
+      itemPda(0).toBase58(),
+    ]);
+  });
+
+  it("User B revokes and closes the TelegramSession", async () => {
+    const [bindingPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("tg_username"), Buffer.from(VALIDATION_USERNAME)],
+      verificationProgram.programId
+    );
+
+    let closedWithoutBinding = false;
+    try {
+      await verificationProgram.methods
+        .closeSession()
+        .accounts({
+          user: otherUser,
+          // @ts-ignore
+          session: sessionPda,
+          rentPayer: otherUser,
+          binding: null,
+          relayerUsage: null,
+        })
+        .signers([otherUserKp])
+        .rpc({ commitment: "confirmed" });
+      closedWithoutBinding = true;
+    } catch (e) {
+      expect(String(e)).to.contain("MissingUsernameBinding");
+    }
+    expect(closedWithoutBinding).to.be.false;
+
+    await verificationProgram.methods
+      .revokeSession()
+      .accounts({
+        user: otherUser,
+        // @ts-ignore
+        session: sessionPda,
+        binding: bindingPda,
+      })
+      .signers([otherUserKp])
+      .rpc({ commitment: "confirmed" });
+
+    const session = await verificationProgram.account.telegramSession.fetch(
+      sessionPda
+    );
+    expect(session.verified).to.be.false;
+    expect(session.verifiedUntil).to.eq(null);
+
+    const binding = await verificationProgram.account.usernameBinding.fetch(
+      bindingPda
+    );
+    expect(binding.session.toBase58()).to.eq(PublicKey.default.toBase58());
+
+    let threw = false;
+    try {
+      await transferProgram.methods
+        .claimDeposit(new BN(1))
+        .accounts({
+          recipient: otherUser,
+          // @ts-ignore
+          deposit: depositPda,
+          session: sessionPda,
+          relayerUsage: null,
+          relayer: null,
+          verificationProgram: null,
+        })
+        .rpc({ commitment: "confirmed" });
+    } catch (e) {
+      threw = true;
+    }
+    expect(threw).to.be.true;
+
+    await verificationProgram.methods
+      .closeSession()
+      .accounts({
+        user: otherUser,
+        // @ts-ignore
+        session: sessionPda,
+        rentPayer: otherUser,
+        binding: null,
+        relayerUsage: null,
+      })
+      .signers([otherUserKp])
+      .rpc({ commitment: "confirmed" });
+
+    const closed = await provider.connection.getAccountInfo(sessionPda);
+    expect(closed).to.eq(null);
   });
 });